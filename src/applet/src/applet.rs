@@ -0,0 +1,168 @@
+/*
+ * Copyright (c) 2026 Antmicro <www.antmicro.com>
+ *
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use std::error;
+use std::fmt;
+use std::io;
+use std::io::{Read, Write};
+
+use clap::Parser;
+
+pub mod errno;
+pub mod kernel;
+
+use kernel::Kernel;
+
+pub const EXIT_FAILURE: i32 = 1;
+/// Exit status of invalid command line arguments, as reported by clap
+pub const EXIT_USAGE: i32 = 2;
+
+/// Sections of the manual page of an applet not covered by its clap
+/// definition.
+pub struct Manual {
+    /// Exit statuses with their meaning
+    pub exit_codes: &'static [(i32, &'static str)],
+    /// Command lines with a description of what they do
+    pub examples: &'static [(&'static str, &'static str)],
+    /// Further sections, by title, for references too long for the
+    /// description, with paragraphs separated by blank lines
+    pub sections: &'static [(&'static str, &'static str)],
+}
+
+/// Error returned by an applet, carrying the exit status of the process
+/// and a message for the user.
+#[derive(Debug)]
+pub struct AppletError {
+    code: i32,
+    message: Option<String>,
+}
+
+pub type AppletResult = Result<(), AppletError>;
+
+impl AppletError {
+    pub fn new<M: Into<String>>(code: i32, message: M) -> Self {
+        Self {
+            code,
+            message: Some(message.into()),
+        }
+    }
+
+    /// Error for applets that already reported the problem themselves
+    /// and only need to set the exit status.
+    pub fn exit(code: i32) -> Self {
+        Self {
+            code,
+            message: None,
+        }
+    }
+
+    /// Error for functionality that needs the wasi kernel, returned when
+    /// built for another target.
+    pub fn unsupported() -> Self {
+        Self::new(EXIT_FAILURE, "unsupported on this platform")
+    }
+
+    pub fn code(&self) -> i32 {
+        self.code
+    }
+
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+}
+
+impl fmt::Display for AppletError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.message {
+            Some(message) => f.write_str(message),
+            None => write!(f, "exit status {}", self.code),
+        }
+    }
+}
+
+impl error::Error for AppletError {}
+
+impl From<io::Error> for AppletError {
+    fn from(e: io::Error) -> Self {
+        Self::new(EXIT_FAILURE, errno::describe(&e))
+    }
+}
+
+/// Standard streams and kernel of an applet invocation.
+///
+/// Applets read and write through these instead of the process streams,
+/// so they can be run in-process with their output captured.
+pub struct Context<'a> {
+    pub stdin: Box<dyn Read + 'a>,
+    pub stdout: Box<dyn Write + 'a>,
+    pub stderr: Box<dyn Write + 'a>,
+    pub kernel: Box<dyn Kernel + 'a>,
+    terminal_stdout: bool,
+}
+
+impl<'a> Context<'a> {
+    pub fn new<I, O, E>(stdin: I, stdout: O, stderr: E) -> Self
+    where
+        I: Read + 'a,
+        O: Write + 'a,
+        E: Write + 'a,
+    {
+        Self {
+            stdin: Box::new(stdin),
+            stdout: Box::new(stdout),
+            stderr: Box::new(stderr),
+            kernel: kernel::system(),
+            terminal_stdout: false,
+        }
+    }
+
+    /// Replaces the system kernel, e.g. with a mock in tests.
+    pub fn with_kernel<K: Kernel + 'a>(mut self, kernel: K) -> Self {
+        self.kernel = Box::new(kernel);
+        self
+    }
+
+    /// Marks stdout as a terminal, which it isn't by default.
+    pub fn with_terminal_stdout(mut self, terminal: bool) -> Self {
+        self.terminal_stdout = terminal;
+        self
+    }
+
+    /// Whether stdout is a terminal, e.g. to refuse writing binary data
+    /// to it.
+    pub fn stdout_is_terminal(&self) -> bool {
+        self.terminal_stdout
+    }
+}
+
+impl Context<'static> {
+    /// Context connected to the standard streams of the process.
+    pub fn std() -> Self {
+        Self::new(io::stdin(), io::stdout(), io::stderr())
+            .with_terminal_stdout(kernel::stdout_is_terminal())
+    }
+}
+
+/// Parses applet arguments with clap.
+///
+/// Help and usage errors are written to the context streams and returned as
+/// an error with clap's exit status, instead of exiting the process.
+pub fn parse_args<P, I, T>(args: I, ctx: &mut Context) -> Result<P, AppletError>
+where
+    P: Parser,
+    I: IntoIterator<Item = T>,
+    T: Into<String>,
+{
+    P::try_parse_from(args.into_iter().map(Into::into)).map_err(|e| {
+        let out = if e.use_stderr() {
+            &mut ctx.stderr
+        } else {
+            &mut ctx.stdout
+        };
+        let _ = write!(out, "{}", e.render());
+        AppletError::exit(e.exit_code())
+    })
+}
@@ -0,0 +1,309 @@
+/*
+ * Copyright (c) 2026 Antmicro <www.antmicro.com>
+ *
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Interface to the services of the wasi kernel.
+//!
+//! Applets that need more than the standard library, like spawning
+//! processes, ioctls on kernel devices or terminal settings, go through
+//! the [`Kernel`] of their [`Context`](crate::Context). On wasi it calls into
+//! `wasi_ext_lib`, on other targets every kernel-specific call fails with an
+//! "unsupported on this platform" error, and tests can provide a mock.
+
+use std::collections::HashMap;
+use std::io;
+use std::io::{Read, Seek, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[cfg(not(target_os = "wasi"))]
+mod host;
+#[cfg(target_os = "wasi")]
+mod wasi;
+
+#[cfg(not(target_os = "wasi"))]
+use host::HostKernel as SystemKernel;
+#[cfg(not(target_os = "wasi"))]
+pub use host::{mkdev, stdout_is_terminal};
+#[cfg(target_os = "wasi")]
+use wasi::WasiKernel as SystemKernel;
+#[cfg(target_os = "wasi")]
+pub use wasi::{mkdev, stdout_is_terminal};
+
+pub type Fd = i32;
+pub type Pid = i32;
+pub type ExitCode = i32;
+
+pub const STDIN_FD: Fd = 0;
+pub const STDOUT_FD: Fd = 1;
+
+/// `dev` argument of [`Kernel::mknod`] creating a fifo.
+pub const FIFO_DEV: i32 = -1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Signal {
+    Hup,
+    Int,
+    Quit,
+    Kill,
+    Usr1,
+    Usr2,
+    Term,
+}
+
+impl Signal {
+    /// Parses a signal name like "TERM" or "SIGTERM", in any case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_uppercase();
+        match name.strip_prefix("SIG").unwrap_or(&name) {
+            "HUP" => Some(Self::Hup),
+            "INT" => Some(Self::Int),
+            "QUIT" => Some(Self::Quit),
+            "KILL" => Some(Self::Kill),
+            "USR1" => Some(Self::Usr1),
+            "USR2" => Some(Self::Usr2),
+            "TERM" => Some(Self::Term),
+            _ => None,
+        }
+    }
+}
+
+/// Redirection of a standard stream of a spawned process.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Redirect {
+    Read(Fd, String),
+    Append(Fd, String),
+}
+
+/// Part of the browser location returned by [`Kernel::uname`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NameType {
+    Href,
+    Protocol,
+    Host,
+    Port,
+    Pathname,
+    Search,
+    Hash,
+    Origin,
+    UserAgent,
+}
+
+/// Device specific requests of [`Device::ioctl`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Ioctl {
+    /// Store the http status of a wget response in the argument
+    WgetStatus,
+    /// Make following reads return the http headers of a wget response
+    WgetHeaders,
+    /// Make following reads return the http body of a wget response
+    WgetBody,
+    /// Make the kernel the writer of a fifo
+    FifoKernelWriter,
+    /// Make the kernel the reader of a fifo
+    FifoKernelReader,
+    /// Remove the fifo once it is closed
+    FifoCloseRemove,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OpenFlags {
+    pub read: bool,
+    pub write: bool,
+    pub create: bool,
+    pub truncate: bool,
+    pub append: bool,
+}
+
+impl OpenFlags {
+    pub const READ: Self = Self {
+        read: true,
+        write: false,
+        create: false,
+        truncate: false,
+        append: false,
+    };
+    pub const WRITE: Self = Self {
+        read: false,
+        write: true,
+        create: false,
+        truncate: false,
+        append: false,
+    };
+    pub const READ_WRITE: Self = Self {
+        read: true,
+        write: true,
+        create: false,
+        truncate: false,
+        append: false,
+    };
+    /// Create the file or truncate an existing one
+    pub const CREATE: Self = Self {
+        read: false,
+        write: true,
+        create: true,
+        truncate: true,
+        append: false,
+    };
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PollEvent {
+    Readable(Fd),
+    Hangup(Fd),
+    /// A child process exited, with its status if the kernel reports it
+    Exited(Pid, Option<ExitCode>),
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WinSize {
+    pub ws_row: u16,
+    pub ws_col: u16,
+}
+
+pub mod termios {
+    //! Terminal flags, with the values used by the wasi kernel.
+
+    pub type Tcflag = u32;
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub struct Termios {
+        pub c_iflag: Tcflag,
+        pub c_oflag: Tcflag,
+        pub c_cflag: Tcflag,
+        pub c_lflag: Tcflag,
+    }
+
+    // c_iflag
+    pub const IGNBRK: Tcflag = 0o1;
+    pub const BRKINT: Tcflag = 0o2;
+    pub const IGNPAR: Tcflag = 0o4;
+    pub const PARMRK: Tcflag = 0o10;
+    pub const INPCK: Tcflag = 0o20;
+    pub const ISTRIP: Tcflag = 0o40;
+    pub const INLCR: Tcflag = 0o100;
+    pub const IGNCR: Tcflag = 0o200;
+    pub const ICRNL: Tcflag = 0o400;
+    pub const IUCLC: Tcflag = 0o1000;
+    pub const IXON: Tcflag = 0o2000;
+    pub const IXANY: Tcflag = 0o4000;
+    pub const IXOFF: Tcflag = 0o10000;
+    pub const IMAXBEL: Tcflag = 0o20000;
+    pub const IUTF8: Tcflag = 0o40000;
+
+    // c_oflag
+    pub const OPOST: Tcflag = 0o1;
+    pub const OLCUC: Tcflag = 0o2;
+    pub const ONLCR: Tcflag = 0o4;
+    pub const OCRNL: Tcflag = 0o10;
+    pub const ONOCR: Tcflag = 0o20;
+    pub const ONLRET: Tcflag = 0o40;
+    pub const OFILL: Tcflag = 0o100;
+    pub const OFDEL: Tcflag = 0o200;
+
+    // c_cflag
+    pub const CSIZE: Tcflag = 0o60;
+    pub const CS5: Tcflag = 0o0;
+    pub const CS6: Tcflag = 0o20;
+    pub const CS7: Tcflag = 0o40;
+    pub const CS8: Tcflag = 0o60;
+    pub const CSTOPB: Tcflag = 0o100;
+    pub const CREAD: Tcflag = 0o200;
+    pub const PARENB: Tcflag = 0o400;
+    pub const PARODD: Tcflag = 0o1000;
+    pub const HUPCL: Tcflag = 0o2000;
+    pub const CLOCAL: Tcflag = 0o4000;
+
+    // c_lflag
+    pub const ISIG: Tcflag = 0o1;
+    pub const ICANON: Tcflag = 0o2;
+    pub const ECHO: Tcflag = 0o10;
+    pub const ECHOE: Tcflag = 0o20;
+    pub const ECHOK: Tcflag = 0o40;
+    pub const ECHONL: Tcflag = 0o100;
+    pub const NOFLSH: Tcflag = 0o200;
+    pub const TOSTOP: Tcflag = 0o400;
+    pub const IEXTEN: Tcflag = 0o100000;
+}
+
+/// File or device opened through a [`Kernel`].
+pub trait Device: Read + Write + Seek {
+    fn ioctl(&mut self, request: Ioctl, arg: Option<&mut i32>) -> io::Result<()>;
+
+    /// Keeps the descriptor from being inherited by spawned processes.
+    fn set_cloexec(&mut self) -> io::Result<()>;
+
+    /// Descriptor identifying the device in [`Kernel::poll`].
+    fn fd(&self) -> Fd;
+}
+
+pub trait Kernel {
+    fn open(&mut self, path: &str, flags: OpenFlags) -> io::Result<Box<dyn Device>>;
+
+    /// Paths of the entries in a directory.
+    fn read_dir(&mut self, path: &str) -> io::Result<Vec<PathBuf>>;
+
+    /// Creates a directory with its missing parents.
+    fn create_dir_all(&mut self, path: &str) -> io::Result<()>;
+
+    /// Renames a file, replacing the destination if it exists.
+    fn rename(&mut self, from: &str, to: &str) -> io::Result<()>;
+
+    fn remove_file(&mut self, path: &str) -> io::Result<()>;
+
+    /// Waits until some of the descriptors can be read from or are hung up,
+    /// or until the timeout passes, in which case no events are returned.
+    /// An error reported for any of the descriptors fails the whole call.
+    fn poll(&mut self, fds: &[Fd], timeout: Option<Duration>) -> io::Result<Vec<PollEvent>>;
+
+    /// Like [`Kernel::poll`], but also waits for the `children` processes
+    /// to exit, returning [`PollEvent::Exited`] events before the others.
+    fn poll_children(
+        &mut self,
+        fds: &[Fd],
+        children: &[Pid],
+        timeout: Option<Duration>,
+    ) -> io::Result<Vec<PollEvent>>;
+
+    /// Reading of a monotonic clock, for measuring timeouts.
+    fn clock(&mut self) -> io::Result<Duration>;
+
+    fn spawn(
+        &mut self,
+        path: &str,
+        args: &[&str],
+        env: &HashMap<String, String>,
+        background: bool,
+        redirects: &[Redirect],
+    ) -> io::Result<(ExitCode, Pid)>;
+
+    fn kill(&mut self, pid: Pid, signal: Signal) -> io::Result<()>;
+
+    fn mknod(&mut self, path: &str, dev: i32) -> io::Result<()>;
+
+    fn mount(
+        &mut self,
+        source: &str,
+        target: &str,
+        fstype: &str,
+        flags: u64,
+        data: &str,
+    ) -> io::Result<()>;
+
+    fn umount(&mut self, target: &str) -> io::Result<()>;
+
+    fn uname(&mut self, name: NameType) -> io::Result<String>;
+
+    fn tcgetattr(&mut self, fd: Fd) -> io::Result<termios::Termios>;
+
+    fn tcsetattr(&mut self, fd: Fd, termios: &termios::Termios) -> io::Result<()>;
+
+    fn tcgetwinsize(&mut self, fd: Fd) -> io::Result<WinSize>;
+}
+
+/// Kernel of the system the applets run on.
+pub fn system() -> Box<dyn Kernel> {
+    Box::new(SystemKernel)
+}
@@ -0,0 +1,180 @@
+/*
+ * Copyright (c) 2026 Antmicro <www.antmicro.com>
+ *
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::io::{IsTerminal, Read, Seek, SeekFrom, Write};
+use std::os::fd::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use super::termios::Termios;
+use super::{
+    Device, ExitCode, Fd, Ioctl, Kernel, NameType, OpenFlags, Pid, PollEvent, Redirect, Signal,
+    WinSize,
+};
+
+// Devices in /dev are the ones of the wasi kernel, like /dev/wget0
+const KERNEL_DEVICES: &str = "/dev";
+
+fn unsupported() -> io::Error {
+    io::Error::new(io::ErrorKind::Unsupported, "unsupported on this platform")
+}
+
+/// Device number of a device file, as `makedev` on Linux.
+pub fn mkdev(major: i32, minor: i32) -> i32 {
+    ((major & 0xfff) << 8) | (minor & 0xff) | ((minor & !0xff) << 12)
+}
+
+/// Whether the standard output of the process is a terminal.
+pub fn stdout_is_terminal() -> bool {
+    io::stdout().is_terminal()
+}
+
+pub struct HostFile(fs::File);
+
+impl Read for HostFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl Write for HostFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+impl Seek for HostFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.0.seek(pos)
+    }
+}
+
+impl Device for HostFile {
+    fn ioctl(&mut self, _request: Ioctl, _arg: Option<&mut i32>) -> io::Result<()> {
+        Err(unsupported())
+    }
+
+    fn set_cloexec(&mut self) -> io::Result<()> {
+        // The standard library opens every file with O_CLOEXEC
+        Ok(())
+    }
+
+    fn fd(&self) -> Fd {
+        self.0.as_raw_fd()
+    }
+}
+
+/// Kernel of targets other than wasi. Regular files can be used, anything
+/// specific to the wasi kernel is unsupported.
+pub struct HostKernel;
+
+impl Kernel for HostKernel {
+    fn open(&mut self, path: &str, flags: OpenFlags) -> io::Result<Box<dyn Device>> {
+        if Path::new(path).starts_with(KERNEL_DEVICES) {
+            return Err(unsupported());
+        }
+        let file = fs::OpenOptions::new()
+            .read(flags.read)
+            .write(flags.write)
+            .create(flags.create)
+            .truncate(flags.truncate)
+            .append(flags.append)
+            .open(path)?;
+        Ok(Box::new(HostFile(file)))
+    }
+
+    fn read_dir(&mut self, path: &str) -> io::Result<Vec<PathBuf>> {
+        fs::read_dir(path)?.map(|entry| Ok(entry?.path())).collect()
+    }
+
+    fn create_dir_all(&mut self, path: &str) -> io::Result<()> {
+        fs::create_dir_all(path)
+    }
+
+    fn rename(&mut self, from: &str, to: &str) -> io::Result<()> {
+        fs::rename(from, to)
+    }
+
+    fn remove_file(&mut self, path: &str) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+
+    fn poll(&mut self, _fds: &[Fd], _timeout: Option<Duration>) -> io::Result<Vec<PollEvent>> {
+        Err(unsupported())
+    }
+
+    fn poll_children(
+        &mut self,
+        _fds: &[Fd],
+        _children: &[Pid],
+        _timeout: Option<Duration>,
+    ) -> io::Result<Vec<PollEvent>> {
+        Err(unsupported())
+    }
+
+    fn clock(&mut self) -> io::Result<Duration> {
+        static START: OnceLock<Instant> = OnceLock::new();
+        Ok(START.get_or_init(Instant::now).elapsed())
+    }
+
+    fn spawn(
+        &mut self,
+        _path: &str,
+        _args: &[&str],
+        _env: &HashMap<String, String>,
+        _background: bool,
+        _redirects: &[Redirect],
+    ) -> io::Result<(ExitCode, Pid)> {
+        Err(unsupported())
+    }
+
+    fn kill(&mut self, _pid: Pid, _signal: Signal) -> io::Result<()> {
+        Err(unsupported())
+    }
+
+    fn mknod(&mut self, _path: &str, _dev: i32) -> io::Result<()> {
+        Err(unsupported())
+    }
+
+    fn mount(
+        &mut self,
+        _source: &str,
+        _target: &str,
+        _fstype: &str,
+        _flags: u64,
+        _data: &str,
+    ) -> io::Result<()> {
+        Err(unsupported())
+    }
+
+    fn umount(&mut self, _target: &str) -> io::Result<()> {
+        Err(unsupported())
+    }
+
+    fn uname(&mut self, _name: NameType) -> io::Result<String> {
+        Err(unsupported())
+    }
+
+    fn tcgetattr(&mut self, _fd: Fd) -> io::Result<Termios> {
+        Err(unsupported())
+    }
+
+    fn tcsetattr(&mut self, _fd: Fd, _termios: &Termios) -> io::Result<()> {
+        Err(unsupported())
+    }
+
+    fn tcgetwinsize(&mut self, _fd: Fd) -> io::Result<WinSize> {
+        Err(unsupported())
+    }
+}
@@ -0,0 +1,335 @@
+/*
+ * Copyright (c) 2026 Antmicro <www.antmicro.com>
+ *
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::mem;
+use std::os::fd::AsRawFd;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use super::termios::Termios;
+use super::{
+    Device, ExitCode, Fd, Ioctl, Kernel, NameType, OpenFlags, Pid, PollEvent, Redirect, Signal,
+    WinSize, STDOUT_FD,
+};
+
+fn errno(e: i32) -> io::Error {
+    io::Error::from_raw_os_error(e)
+}
+
+// The kernel doesn't notify about exits of background children, so
+// poll_children looks them up in /proc this often
+const CHILDREN_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+// Whether the process is gone from /proc or left as a zombie. The state
+// follows the command name, which is in parentheses and can contain spaces.
+fn has_exited(pid: Pid) -> io::Result<bool> {
+    match fs::read_to_string(format!("/proc/{}/stat", pid)) {
+        Ok(stat) => Ok(stat
+            .rsplit_once(") ")
+            .is_some_and(|(_, fields)| fields.starts_with(['Z', 'X']))),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(true),
+        Err(e) => Err(e),
+    }
+}
+
+pub fn mkdev(major: i32, minor: i32) -> i32 {
+    wasi_ext_lib::mkdev(major, minor)
+}
+
+/// Whether the standard output of the process is a terminal.
+pub fn stdout_is_terminal() -> bool {
+    wasi_ext_lib::isatty(STDOUT_FD as wasi_ext_lib::Fd).unwrap_or(false)
+}
+
+pub struct WasiDevice(fs::File);
+
+impl Read for WasiDevice {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl Write for WasiDevice {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+impl Seek for WasiDevice {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.0.seek(pos)
+    }
+}
+
+impl Device for WasiDevice {
+    fn ioctl(&mut self, request: Ioctl, arg: Option<&mut i32>) -> io::Result<()> {
+        let request = match request {
+            Ioctl::WgetStatus => wasi_ext_lib::WGETGS,
+            Ioctl::WgetHeaders => wasi_ext_lib::WGETRH,
+            Ioctl::WgetBody => wasi_ext_lib::WGETRB,
+            Ioctl::FifoKernelWriter => wasi_ext_lib::FIFOSKERNW,
+            Ioctl::FifoKernelReader => wasi_ext_lib::FIFOSKERNR,
+            Ioctl::FifoCloseRemove => wasi_ext_lib::FIFOSCLOSERM,
+        };
+        match arg {
+            Some(arg) => wasi_ext_lib::ioctl(self.fd(), request, Some(arg)),
+            None => wasi_ext_lib::ioctl::<()>(self.fd(), request, None),
+        }
+        .map_err(errno)
+    }
+
+    fn set_cloexec(&mut self) -> io::Result<()> {
+        wasi_ext_lib::fcntl(
+            self.fd() as wasi_ext_lib::Fd,
+            wasi_ext_lib::FcntlCommand::F_SETFD {
+                flags: wasi_ext_lib::WASI_EXT_FDFLAG_CLOEXEC,
+            },
+        )
+        .map_err(errno)?;
+        Ok(())
+    }
+
+    fn fd(&self) -> Fd {
+        self.0.as_raw_fd()
+    }
+}
+
+pub struct WasiKernel;
+
+impl Kernel for WasiKernel {
+    fn open(&mut self, path: &str, flags: OpenFlags) -> io::Result<Box<dyn Device>> {
+        let file = fs::OpenOptions::new()
+            .read(flags.read)
+            .write(flags.write)
+            .create(flags.create)
+            .truncate(flags.truncate)
+            .append(flags.append)
+            .open(path)?;
+        Ok(Box::new(WasiDevice(file)))
+    }
+
+    fn read_dir(&mut self, path: &str) -> io::Result<Vec<PathBuf>> {
+        fs::read_dir(path)?.map(|entry| Ok(entry?.path())).collect()
+    }
+
+    fn create_dir_all(&mut self, path: &str) -> io::Result<()> {
+        fs::create_dir_all(path)
+    }
+
+    fn rename(&mut self, from: &str, to: &str) -> io::Result<()> {
+        fs::rename(from, to)
+    }
+
+    fn remove_file(&mut self, path: &str) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+
+    fn poll(&mut self, fds: &[Fd], timeout: Option<Duration>) -> io::Result<Vec<PollEvent>> {
+        let mut subs = fds
+            .iter()
+            .enumerate()
+            .map(|(i, fd)| wasi::Subscription {
+                userdata: i as u64,
+                u: wasi::SubscriptionU {
+                    tag: wasi::EVENTTYPE_FD_READ.raw(),
+                    u: wasi::SubscriptionUU {
+                        fd_read: wasi::SubscriptionFdReadwrite {
+                            file_descriptor: *fd as u32,
+                        },
+                    },
+                },
+            })
+            .collect::<Vec<_>>();
+        if let Some(timeout) = timeout {
+            subs.push(wasi::Subscription {
+                userdata: fds.len() as u64,
+                u: wasi::SubscriptionU {
+                    tag: wasi::EVENTTYPE_CLOCK.raw(),
+                    u: wasi::SubscriptionUU {
+                        clock: wasi::SubscriptionClock {
+                            id: wasi::CLOCKID_MONOTONIC,
+                            timeout: timeout.as_nanos() as u64,
+                            precision: 0,
+                            flags: 0,
+                        },
+                    },
+                },
+            });
+        }
+        let mut events: Vec<wasi::Event> = vec![unsafe { mem::zeroed() }; subs.len()];
+
+        let count = unsafe { wasi::poll_oneoff(subs.as_ptr(), events.as_mut_ptr(), subs.len()) }
+            .map_err(|e| errno(e.raw() as i32))?;
+
+        let mut polled = Vec::new();
+        for event in events[..count]
+            .iter()
+            .filter(|event| event.type_ != wasi::EVENTTYPE_CLOCK)
+        {
+            if event.error != wasi::ERRNO_SUCCESS {
+                return Err(errno(event.error.raw() as i32));
+            }
+            let fd = fds[event.userdata as usize];
+            if event.fd_readwrite.flags & wasi::EVENTRWFLAGS_FD_READWRITE_HANGUP != 0 {
+                polled.push(PollEvent::Hangup(fd));
+            } else {
+                polled.push(PollEvent::Readable(fd));
+            }
+        }
+        Ok(polled)
+    }
+
+    fn poll_children(
+        &mut self,
+        fds: &[Fd],
+        children: &[Pid],
+        timeout: Option<Duration>,
+    ) -> io::Result<Vec<PollEvent>> {
+        if children.is_empty() {
+            return self.poll(fds, timeout);
+        }
+        let deadline = match timeout {
+            Some(timeout) => Some(self.clock()? + timeout),
+            None => None,
+        };
+        loop {
+            let mut events = Vec::new();
+            for pid in children {
+                if has_exited(*pid)? {
+                    events.push(PollEvent::Exited(*pid, None));
+                }
+            }
+            let now = self.clock()?;
+            let wait = if !events.is_empty() {
+                Duration::ZERO
+            } else if let Some(deadline) = deadline {
+                deadline.saturating_sub(now).min(CHILDREN_CHECK_INTERVAL)
+            } else {
+                CHILDREN_CHECK_INTERVAL
+            };
+            events.extend(self.poll(fds, Some(wait))?);
+            if !events.is_empty() || deadline.is_some_and(|deadline| now + wait >= deadline) {
+                return Ok(events);
+            }
+        }
+    }
+
+    fn clock(&mut self) -> io::Result<Duration> {
+        let time = unsafe { wasi::clock_time_get(wasi::CLOCKID_MONOTONIC, 1) }
+            .map_err(|e| errno(e.raw() as i32))?;
+        Ok(Duration::from_nanos(time))
+    }
+
+    fn spawn(
+        &mut self,
+        path: &str,
+        args: &[&str],
+        env: &HashMap<String, String>,
+        background: bool,
+        redirects: &[Redirect],
+    ) -> io::Result<(ExitCode, Pid)> {
+        let redirects = redirects
+            .iter()
+            .map(|redirect| match redirect {
+                Redirect::Read(fd, path) => {
+                    wasi_ext_lib::Redirect::Read(*fd as wasi_ext_lib::Fd, path.clone())
+                }
+                Redirect::Append(fd, path) => {
+                    wasi_ext_lib::Redirect::Append(*fd as wasi_ext_lib::Fd, path.clone())
+                }
+            })
+            .collect::<Vec<_>>();
+        wasi_ext_lib::spawn(path, args, env, background, &redirects).map_err(errno)
+    }
+
+    fn kill(&mut self, pid: Pid, signal: Signal) -> io::Result<()> {
+        let signal = match signal {
+            Signal::Hup => wasi::SIGNAL_HUP,
+            Signal::Int => wasi::SIGNAL_INT,
+            Signal::Quit => wasi::SIGNAL_QUIT,
+            Signal::Kill => wasi::SIGNAL_KILL,
+            Signal::Usr1 => wasi::SIGNAL_USR1,
+            Signal::Usr2 => wasi::SIGNAL_USR2,
+            Signal::Term => wasi::SIGNAL_TERM,
+        };
+        wasi_ext_lib::kill(pid, signal).map_err(errno)
+    }
+
+    fn mknod(&mut self, path: &str, dev: i32) -> io::Result<()> {
+        wasi_ext_lib::mknod(path, dev).map_err(errno)
+    }
+
+    fn mount(
+        &mut self,
+        source: &str,
+        target: &str,
+        fstype: &str,
+        flags: u64,
+        data: &str,
+    ) -> io::Result<()> {
+        wasi_ext_lib::mount(source, target, fstype, flags, data).map_err(errno)
+    }
+
+    fn umount(&mut self, target: &str) -> io::Result<()> {
+        wasi_ext_lib::umount(target).map_err(errno)
+    }
+
+    fn uname(&mut self, name: NameType) -> io::Result<String> {
+        let name = match name {
+            NameType::Href => wasi_ext_lib::NameType::Href,
+            NameType::Protocol => wasi_ext_lib::NameType::Protocol,
+            NameType::Host => wasi_ext_lib::NameType::Host,
+            NameType::Port => wasi_ext_lib::NameType::Port,
+            NameType::Pathname => wasi_ext_lib::NameType::Pathname,
+            NameType::Search => wasi_ext_lib::NameType::Search,
+            NameType::Hash => wasi_ext_lib::NameType::Hash,
+            NameType::Origin => wasi_ext_lib::NameType::Origin,
+            NameType::UserAgent => wasi_ext_lib::NameType::UserAgent,
+        };
+        wasi_ext_lib::uname(name).map_err(errno)
+    }
+
+    fn tcgetattr(&mut self, fd: Fd) -> io::Result<Termios> {
+        let termios = wasi_ext_lib::tcgetattr(fd as wasi_ext_lib::Fd).map_err(errno)?;
+        Ok(Termios {
+            c_iflag: termios.c_iflag,
+            c_oflag: termios.c_oflag,
+            c_cflag: termios.c_cflag,
+            c_lflag: termios.c_lflag,
+        })
+    }
+
+    fn tcsetattr(&mut self, fd: Fd, termios: &Termios) -> io::Result<()> {
+        // Keep the fields that are not exposed, like the control characters
+        let mut termios_p = wasi_ext_lib::tcgetattr(fd as wasi_ext_lib::Fd).map_err(errno)?;
+        termios_p.c_iflag = termios.c_iflag;
+        termios_p.c_oflag = termios.c_oflag;
+        termios_p.c_cflag = termios.c_cflag;
+        termios_p.c_lflag = termios.c_lflag;
+        wasi_ext_lib::tcsetattr(
+            fd as wasi_ext_lib::Fd,
+            wasi_ext_lib::TcsetattrAction::TCSANOW,
+            &termios_p,
+        )
+        .map_err(errno)
+    }
+
+    fn tcgetwinsize(&mut self, fd: Fd) -> io::Result<WinSize> {
+        let size = wasi_ext_lib::tcgetwinsize(fd as wasi_ext_lib::Fd).map_err(errno)?;
+        Ok(WinSize {
+            ws_row: size.ws_row,
+            ws_col: size.ws_col,
+        })
+    }
+}
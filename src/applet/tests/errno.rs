@@ -0,0 +1,37 @@
+/*
+ * Copyright (c) 2026 Antmicro <www.antmicro.com>
+ *
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use std::io;
+
+use applet::errno::{self, WithPath};
+use applet::AppletError;
+
+#[test]
+fn describes_wasi_errno() {
+    assert_eq!(errno::strerror(errno::ENOENT), "No such file or directory");
+    assert_eq!(errno::strerror(errno::ESRCH), "No such process");
+    assert_eq!(errno::strerror(1000), "Unknown error");
+}
+
+#[test]
+fn prefixes_path() {
+    let result: io::Result<()> = Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        errno::strerror(errno::ENOENT),
+    ));
+
+    let e = AppletError::from(result.with_path("/etc/init.d").unwrap_err());
+
+    assert_eq!(e.code(), 1);
+    assert_eq!(e.message(), Some("/etc/init.d: No such file or directory"));
+}
+
+#[test]
+fn omits_os_error_number() {
+    let e = io::Error::from_raw_os_error(2);
+
+    assert!(!errno::describe(&e).contains("os error"));
+}
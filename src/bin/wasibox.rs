@@ -5,49 +5,181 @@
  */
 
 use std::env;
-use std::io;
+use std::io::Write;
+use std::path::Path;
 use std::process;
 
+use applet::{AppletError, AppletResult, Context, EXIT_FAILURE};
+use wasibox::completions;
+use wasibox::help;
+use wasibox::man;
 pub use wasibox::tools_map::AppletType;
-use wasibox::tools_map::TOOLS_MAP;
+use wasibox::tools_map::{applet_name, find_applet, suggest_applet};
 
-fn get_applet(args: &mut env::Args) -> Result<&AppletType, &'static str> {
+const NAME: &str = env!("CARGO_PKG_NAME");
+
+fn get_applet(args: &mut env::Args) -> Result<(&'static str, AppletType), String> {
     let command = if let Some(cmd) = args.next() {
-        if cmd == env!("CARGO_PKG_NAME") || cmd == format!("{}.wasm", env!("CARGO_PKG_NAME")) {
+        if applet_name(&cmd) == NAME {
             if let Some(cmd_) = args.next() {
-                cmd_
+                match cmd_.as_str() {
+                    "--install" => return Ok((NAME, install)),
+                    "-h" | "--help" | "help" => return Ok((NAME, help)),
+                    "--list" => return Ok((NAME, list)),
+                    "--version" => return Ok((NAME, version)),
+                    "completions" => return Ok((NAME, completions)),
+                    "man" => return Ok((NAME, man)),
+                    _ => cmd_,
+                }
             } else {
-                return Err("Missing tool name");
+                return Err(String::from("Missing tool name"));
             }
         } else {
             cmd
         }
     } else {
-        return Err("Missing command line arguments");
+        return Err(String::from("Missing command line arguments"));
     };
 
-    if let Some(applet) = TOOLS_MAP.get(&command[..]) {
-        Ok(applet)
+    let name = applet_name(&command);
+    if let Some(applet) = find_applet(name) {
+        Ok((applet.name, applet.main))
+    } else if let Some(suggestion) = suggest_applet(name) {
+        Err(format!(
+            "No such tool: {}, did you mean {}?",
+            name, suggestion
+        ))
+    } else {
+        Err(format!("No such tool: {}", name))
+    }
+}
+
+fn install(args: Vec<String>, ctx: &mut Context) -> AppletResult {
+    let mut force = false;
+    let mut list = false;
+    let mut dir = None;
+
+    for arg in args {
+        match arg.as_str() {
+            "-f" | "--force" => force = true,
+            "-l" | "--list" => list = true,
+            _ if arg.starts_with('-') || dir.is_some() => {
+                return Err(AppletError::new(
+                    EXIT_FAILURE,
+                    format!("Unexpected argument: {}", arg),
+                ));
+            }
+            _ => dir = Some(arg),
+        }
+    }
+
+    if let Some(dir) = dir {
+        wasibox::install::install(
+            &mut ctx.stdout,
+            Path::new(&dir),
+            &wasibox::install::binary_path(),
+            force,
+            list,
+        )?;
+        Ok(())
+    } else {
+        Err(AppletError::new(EXIT_FAILURE, "Missing install directory"))
+    }
+}
+
+fn help(args: Vec<String>, ctx: &mut Context) -> AppletResult {
+    if let Some(tool) = args.first() {
+        if let Some(applet) = find_applet(applet_name(tool)) {
+            help::write_applet_help(&mut ctx.stdout, applet)?;
+            Ok(())
+        } else {
+            Err(AppletError::new(
+                EXIT_FAILURE,
+                format!("No such tool: {}", tool),
+            ))
+        }
+    } else {
+        help::write_version(&mut ctx.stdout)?;
+        writeln!(ctx.stdout)?;
+        help::write_usage(&mut ctx.stdout)?;
+        writeln!(ctx.stdout)?;
+        help::write_listing(&mut ctx.stdout)?;
+        Ok(())
+    }
+}
+
+fn man(args: Vec<String>, ctx: &mut Context) -> AppletResult {
+    if let Some(tool) = args.first() {
+        if let Some(applet) = find_applet(applet_name(tool)) {
+            man::write_manual(&mut ctx.stdout, applet)?;
+            Ok(())
+        } else {
+            Err(AppletError::new(
+                EXIT_FAILURE,
+                format!("No such tool: {}", tool),
+            ))
+        }
     } else {
-        Err("No such tool")
+        Err(AppletError::new(EXIT_FAILURE, "Missing tool name"))
     }
 }
 
-fn main() -> io::Result<()> {
+fn list(_args: Vec<String>, ctx: &mut Context) -> AppletResult {
+    help::write_names(&mut ctx.stdout)?;
+    Ok(())
+}
+
+fn version(_args: Vec<String>, ctx: &mut Context) -> AppletResult {
+    help::write_version(&mut ctx.stdout)?;
+    Ok(())
+}
+
+fn completions(args: Vec<String>, ctx: &mut Context) -> AppletResult {
+    match args.first() {
+        Some(name) => {
+            if let Some(shell) = completions::shell(name) {
+                completions::write_completions(&mut ctx.stdout, shell);
+                Ok(())
+            } else {
+                Err(AppletError::new(
+                    EXIT_FAILURE,
+                    format!(
+                        "Unsupported shell: {}, expected one of: {}",
+                        name,
+                        completions::SHELLS.join(", ")
+                    ),
+                ))
+            }
+        }
+        None => Err(AppletError::new(EXIT_FAILURE, "Missing shell name")),
+    }
+}
+
+fn main() {
     let mut args = env::args();
+    #[cfg(target_os = "wasi")]
     let _ = wasi_ext_lib::chdir(match wasi_ext_lib::getcwd() {
         Ok(p) => p,
         Err(_) => String::from("/"),
     });
 
     match get_applet(&mut args) {
-        Ok(applet) => applet(args),
+        Ok((name, applet)) => {
+            if let Err(e) = applet(args.collect(), &mut Context::std()) {
+                // Errors already reported by the applet, e.g. usage, carry no message
+                if let Some(message) = e.message() {
+                    eprintln!("{}: {}", name, message);
+                }
+                process::exit(e.code());
+            }
+        }
         Err(e) => {
+            let mut stderr = std::io::stderr();
             eprintln!("{}\n", e);
-            eprintln!("USAGE: {} [tool [arguments]]", env!("CARGO_PKG_NAME"));
-            eprintln!("   or: tool [arguments]\n");
-            eprintln!("Available tools are: {:?}", TOOLS_MAP.keys());
-            process::exit(1);
+            let _ = help::write_usage(&mut stderr);
+            eprintln!();
+            let _ = help::write_listing(&mut stderr);
+            process::exit(EXIT_FAILURE);
         }
     }
 }
@@ -0,0 +1,94 @@
+/*
+ * Copyright (c) 2026 Antmicro <www.antmicro.com>
+ *
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use std::io::Write;
+use std::iter;
+
+use clap::{Arg, ArgAction, Command, ValueHint};
+use clap_complete::Shell;
+
+use crate::tools_map::{Applet, APPLETS};
+
+const NAME: &str = env!("CARGO_PKG_NAME");
+
+/// Names of the shells completion scripts can be generated for.
+pub const SHELLS: &[&str] = &["bash", "zsh", "fish"];
+
+pub fn shell(name: &str) -> Option<Shell> {
+    match name {
+        "bash" => Some(Shell::Bash),
+        "zsh" => Some(Shell::Zsh),
+        "fish" => Some(Shell::Fish),
+        _ => None,
+    }
+}
+
+fn applet_command(applet: &Applet) -> Command {
+    (applet.command)().name(applet.name)
+}
+
+fn wasibox_command() -> Command {
+    let names = APPLETS
+        .iter()
+        .map(|applet| applet.name)
+        .collect::<Vec<&str>>();
+
+    Command::new(NAME)
+        .version(env!("CARGO_PKG_VERSION"))
+        .disable_help_subcommand(true)
+        .subcommands(APPLETS.iter().map(applet_command))
+        .subcommand(
+            Command::new("help")
+                .about("Print help of a tool")
+                .arg(Arg::new("tool").value_parser(names.clone())),
+        )
+        .subcommand(
+            Command::new("man")
+                .about("Print the manual page of a tool")
+                .arg(Arg::new("tool").required(true).value_parser(names)),
+        )
+        .subcommand(
+            Command::new("completions")
+                .about("Print a shell completion script")
+                .arg(
+                    Arg::new("shell")
+                        .required(true)
+                        .value_parser(SHELLS.to_vec()),
+                ),
+        )
+        .arg(
+            Arg::new("install")
+                .long("install")
+                .value_name("DIR")
+                .value_hint(ValueHint::DirPath)
+                .help("Create links to the binary for every tool"),
+        )
+        .arg(
+            Arg::new("force")
+                .long("force")
+                .action(ArgAction::SetTrue)
+                .requires("install")
+                .help("Replace existing files when installing"),
+        )
+        .arg(
+            Arg::new("list")
+                .long("list")
+                .action(ArgAction::SetTrue)
+                .help("Print the names of the tools"),
+        )
+}
+
+/// Writes completions for wasibox itself and for every applet, including
+/// links named after their aliases.
+pub fn write_completions(out: &mut dyn Write, shell: Shell) {
+    clap_complete::generate(shell, &mut wasibox_command(), NAME, out);
+    for applet in APPLETS {
+        let mut command = applet_command(applet);
+        for name in iter::once(&applet.name).chain(applet.aliases) {
+            clap_complete::generate(shell, &mut command, *name, out);
+        }
+    }
+}
@@ -0,0 +1,72 @@
+/*
+ * Copyright (c) 2026 Antmicro <www.antmicro.com>
+ *
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use std::io;
+use std::io::Write;
+
+use crate::tools_map::{Applet, APPLETS};
+
+fn sorted_applets() -> Vec<&'static Applet> {
+    let mut applets = APPLETS.iter().collect::<Vec<_>>();
+    applets.sort_by_key(|applet| applet.name);
+    applets
+}
+
+pub fn write_usage<W: Write>(out: &mut W) -> io::Result<()> {
+    let name = env!("CARGO_PKG_NAME");
+    writeln!(out, "USAGE: {} [tool [arguments]]", name)?;
+    writeln!(out, "   or: {} --install [--force] [--list] <dir>", name)?;
+    writeln!(out, "   or: {} --help | --list | --version", name)?;
+    writeln!(out, "   or: {} help <tool>", name)?;
+    writeln!(out, "   or: {} man <tool>", name)?;
+    writeln!(out, "   or: {} completions <bash|zsh|fish>", name)?;
+    writeln!(out, "   or: tool [arguments]")
+}
+
+/// Writes the alphabetised listing of compiled-in applets with their summaries.
+pub fn write_listing<W: Write>(out: &mut W) -> io::Result<()> {
+    let applets = sorted_applets();
+    let width = applets.iter().map(|a| a.name.len()).max().unwrap_or(0);
+
+    writeln!(out, "Available tools:")?;
+    for applet in applets {
+        writeln!(out, "    {:width$}  {}", applet.name, applet.summary())?;
+    }
+    Ok(())
+}
+
+/// Writes the names of compiled-in applets, one per line.
+pub fn write_names<W: Write>(out: &mut W) -> io::Result<()> {
+    for applet in sorted_applets() {
+        writeln!(out, "{}", applet.name)?;
+    }
+    Ok(())
+}
+
+pub fn write_version<W: Write>(out: &mut W) -> io::Result<()> {
+    writeln!(
+        out,
+        "{} {}",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION")
+    )
+}
+
+pub fn write_applet_help<W: Write>(out: &mut W, applet: &Applet) -> io::Result<()> {
+    writeln!(out, "{} {}", applet.name, applet.version)?;
+    if !applet.aliases.is_empty() {
+        writeln!(out, "Aliases: {}", applet.aliases.join(", "))?;
+    }
+    writeln!(out)?;
+    write!(
+        out,
+        "{}",
+        (applet.command)()
+            .name(applet.name)
+            .bin_name(applet.name)
+            .render_long_help()
+    )
+}
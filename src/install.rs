@@ -0,0 +1,145 @@
+/*
+ * Copyright (c) 2026 Antmicro <www.antmicro.com>
+ *
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use applet::errno::WithPath;
+
+use crate::tools_map::TOOLS_MAP;
+
+const DEFAULT_BINARY_PATH: &str = "/usr/bin/wasibox";
+
+/// Path of the running multicall binary that the applet links should point to.
+///
+/// `current_exe` is not available on wasi, so argv[0] is used when it is a path
+/// and the default install location otherwise.
+pub fn binary_path() -> PathBuf {
+    if let Ok(path) = env::current_exe() {
+        return path;
+    }
+    arg_binary_path(env::args().next())
+}
+
+/// Path of the binary named by argv[0], made absolute so that links pointing
+/// to it don't depend on their own directory, or the default install location
+/// when argv[0] is only a name.
+pub fn arg_binary_path(arg: Option<String>) -> PathBuf {
+    match arg {
+        Some(arg) if arg.contains('/') => match env::current_dir() {
+            // Joining also drops the `.` components of the path
+            Ok(dir) => dir.join(arg).components().collect(),
+            Err(_) => PathBuf::from(arg),
+        },
+        _ => PathBuf::from(DEFAULT_BINARY_PATH),
+    }
+}
+
+#[cfg(target_os = "wasi")]
+fn symlink(original: &Path, link: &Path) -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    let invalid = || io::Error::new(io::ErrorKind::InvalidInput, "Invalid link path");
+    let dir = fs::File::open(match link.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    })?;
+    let name = link
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(invalid)?;
+    let original = original.to_str().ok_or_else(invalid)?;
+
+    unsafe { wasi::path_symlink(original, dir.as_raw_fd() as u32, name) }
+        .map_err(|e| io::Error::from_raw_os_error(e.raw() as i32))
+}
+
+#[cfg(unix)]
+fn symlink(original: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(original, link)
+}
+
+#[cfg(not(any(unix, target_os = "wasi")))]
+fn symlink(_original: &Path, _link: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Symlinks are not supported",
+    ))
+}
+
+// Not every filesystem in the runtime supports symlinks, so fall back to
+// a hardlink and finally to a plain copy of the binary.
+fn create_link(original: &Path, link: &Path) -> io::Result<()> {
+    symlink(original, link)
+        .or_else(|_| fs::hard_link(original, link))
+        .or_else(|_| fs::copy(original, link).map(|_| ()))
+}
+
+// The link is created under a temporary name and renamed over the target,
+// so the old file stays in place if the new link cannot be created.
+fn replace_link(original: &Path, link: &Path) -> io::Result<()> {
+    let mut name = OsString::from(".");
+    name.push(link.file_name().unwrap_or_default());
+    name.push(".new");
+    let tmp = link.with_file_name(name);
+
+    let _ = fs::remove_file(&tmp);
+    create_link(original, &tmp).with_path(tmp.display())?;
+    fs::rename(&tmp, link)
+        .with_path(link.display())
+        .inspect_err(|_| {
+            let _ = fs::remove_file(&tmp);
+        })
+}
+
+/// Creates a link to `binary` in `dir` for every registered applet and alias.
+///
+/// Existing files are only replaced when `force` is set. With `list` the link
+/// paths are written to `out` and nothing is created.
+pub fn install<W: Write>(
+    out: &mut W,
+    dir: &Path,
+    binary: &Path,
+    force: bool,
+    list: bool,
+) -> io::Result<()> {
+    if !list && !dir.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{}: Not a directory", dir.display()),
+        ));
+    }
+
+    for name in TOOLS_MAP.keys() {
+        let link = dir.join(name);
+        if list {
+            writeln!(out, "{}", link.display())?;
+            continue;
+        }
+
+        match fs::symlink_metadata(&link) {
+            Ok(metadata) if metadata.is_dir() => {
+                return Err(io::Error::new(
+                    io::ErrorKind::IsADirectory,
+                    format!("{}: Is a directory", link.display()),
+                ));
+            }
+            Ok(_) if force => replace_link(binary, &link)?,
+            Ok(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("{}: File exists, use --force to replace it", link.display()),
+                ));
+            }
+            Err(_) => create_link(binary, &link).with_path(link.display())?,
+        }
+    }
+
+    Ok(())
+}
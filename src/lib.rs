@@ -4,4 +4,8 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 
+pub mod completions;
+pub mod help;
+pub mod install;
+pub mod man;
 pub mod tools_map;
@@ -0,0 +1,256 @@
+/*
+ * Copyright (c) 2026 Antmicro <www.antmicro.com>
+ *
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Manual pages of the applets, rendered as text in the layout of man(1),
+//! since the browser environment has no man pages.
+
+use std::io;
+use std::io::Write;
+
+use clap::{Arg, Command};
+
+use crate::tools_map::Applet;
+
+const WIDTH: usize = 80;
+const INDENT: usize = 7;
+// Indentation of the descriptions of options, exit codes and examples
+const ITEM_INDENT: usize = 14;
+
+fn write_wrapped<W: Write>(out: &mut W, text: &str, indent: usize) -> io::Result<()> {
+    for (i, paragraph) in text.split("\n\n").enumerate() {
+        if i > 0 {
+            writeln!(out)?;
+        }
+        let mut column = 0;
+        for word in paragraph.split_whitespace() {
+            if column > 0 && column + 1 + word.len() > WIDTH {
+                writeln!(out)?;
+                column = 0;
+            }
+            if column == 0 {
+                write!(out, "{:indent$}{}", "", word)?;
+                column = indent + word.len();
+            } else {
+                write!(out, " {}", word)?;
+                column += 1 + word.len();
+            }
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+fn write_section<W: Write>(out: &mut W, title: &str) -> io::Result<()> {
+    writeln!(out)?;
+    writeln!(out, "{}", title)
+}
+
+/// Writes a tagged paragraph, with the description next to the tag when
+/// it is short enough, like `.TP` in roff.
+fn write_item<W: Write>(out: &mut W, tag: &str, text: &str) -> io::Result<()> {
+    let mut description = Vec::new();
+    write_wrapped(&mut description, text, ITEM_INDENT)?;
+
+    if INDENT + tag.len() < ITEM_INDENT && description.len() > ITEM_INDENT {
+        write!(
+            out,
+            "{:INDENT$}{:<width$}",
+            "",
+            tag,
+            width = ITEM_INDENT - INDENT
+        )?;
+        out.write_all(&description[ITEM_INDENT..])?;
+    } else {
+        writeln!(out, "{:INDENT$}{}", "", tag)?;
+        out.write_all(&description)?;
+    }
+    Ok(())
+}
+
+fn arg_tag(arg: &Arg) -> String {
+    let value = if arg.get_action().takes_values() {
+        let names = match arg.get_value_names() {
+            Some(names) => names.iter().map(ToString::to_string).collect(),
+            None => vec![arg.get_id().to_string().to_uppercase()],
+        };
+        let multiple = arg
+            .get_num_args()
+            .is_some_and(|num_args| num_args.max_values() > 1);
+        format!(
+            "<{}>{}",
+            names.join("> <"),
+            if multiple { "..." } else { "" }
+        )
+    } else {
+        String::new()
+    };
+
+    if arg.is_positional() {
+        return value;
+    }
+    let names = arg
+        .get_short()
+        .map(|short| format!("-{}", short))
+        .into_iter()
+        .chain(arg.get_long().map(|long| format!("--{}", long)))
+        .collect::<Vec<String>>()
+        .join(", ");
+    if value.is_empty() {
+        names
+    } else {
+        format!("{} {}", names, value)
+    }
+}
+
+fn arg_description(arg: &Arg) -> String {
+    let mut description = arg
+        .get_long_help()
+        .or(arg.get_help())
+        .map(ToString::to_string)
+        .unwrap_or_default();
+    let values = arg
+        .get_possible_values()
+        .into_iter()
+        .filter(|value| !value.is_hide_set())
+        .map(|value| value.get_name().to_string())
+        .collect::<Vec<String>>();
+    if !values.is_empty() {
+        description.push_str(&format!("\n\nPossible values: {}", values.join(", ")));
+    }
+    description
+}
+
+fn write_args<'a, W: Write>(
+    out: &mut W,
+    title: &str,
+    args: impl Iterator<Item = &'a Arg>,
+) -> io::Result<()> {
+    let mut args = args.filter(|arg| !arg.is_hide_set()).peekable();
+    if args.peek().is_some() {
+        write_section(out, title)?;
+        for arg in args {
+            write_item(out, &arg_tag(arg), &arg_description(arg))?;
+        }
+    }
+    Ok(())
+}
+
+fn write_subcommands<W: Write>(out: &mut W, command: &Command) -> io::Result<()> {
+    let mut subcommands = command
+        .get_subcommands()
+        .filter(|subcommand| !subcommand.is_hide_set())
+        .peekable();
+    if subcommands.peek().is_some() {
+        write_section(out, "COMMANDS")?;
+        for subcommand in subcommands {
+            let usage = subcommand.clone().render_usage().to_string();
+            let usage = usage.trim().strip_prefix("Usage:").unwrap_or(&usage).trim();
+            let tag = usage
+                .strip_prefix(command.get_name())
+                .unwrap_or(usage)
+                .trim();
+            let about = subcommand
+                .get_about()
+                .map(ToString::to_string)
+                .unwrap_or_default();
+            write_item(out, tag, &about)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_header<W: Write>(out: &mut W, left: &str, center: &str, right: &str) -> io::Result<()> {
+    let padding = WIDTH.saturating_sub(left.len() + center.len() + right.len());
+    writeln!(
+        out,
+        "{left}{:before$}{center}{:after$}{right}",
+        "",
+        "",
+        before = padding / 2,
+        after = padding - padding / 2
+    )
+}
+
+pub fn write_manual<W: Write>(out: &mut W, applet: &Applet) -> io::Result<()> {
+    let mut command: Command = (applet.command)().name(applet.name).bin_name(applet.name);
+    command.build();
+
+    let title = format!("{}(1)", applet.name.to_uppercase());
+    write_header(out, &title, "Wasibox Manual", &title)?;
+
+    let about = command
+        .get_about()
+        .map(ToString::to_string)
+        .unwrap_or_default();
+    let names = [applet.name]
+        .iter()
+        .chain(applet.aliases)
+        .copied()
+        .collect::<Vec<&str>>()
+        .join(", ");
+    write_section(out, "NAME")?;
+    write_wrapped(out, &format!("{} - {}", names, about), INDENT)?;
+
+    let usage = command.render_usage().to_string();
+    write_section(out, "SYNOPSIS")?;
+    write_wrapped(
+        out,
+        usage.trim().strip_prefix("Usage:").unwrap_or(&usage),
+        INDENT,
+    )?;
+
+    // The long description repeats the summary as its first paragraph
+    let description = command
+        .get_long_about()
+        .map(ToString::to_string)
+        .unwrap_or_else(|| about.clone());
+    write_section(out, "DESCRIPTION")?;
+    write_wrapped(
+        out,
+        description
+            .strip_prefix(&about)
+            .unwrap_or(&description)
+            .trim(),
+        INDENT,
+    )?;
+
+    write_subcommands(out, &command)?;
+    write_args(
+        out,
+        "ARGUMENTS",
+        command.get_arguments().filter(|arg| arg.is_positional()),
+    )?;
+    write_args(
+        out,
+        "OPTIONS",
+        command.get_arguments().filter(|arg| !arg.is_positional()),
+    )?;
+
+    for (title, text) in applet.manual.sections {
+        write_section(out, title)?;
+        write_wrapped(out, text, INDENT)?;
+    }
+
+    write_section(out, "EXIT STATUS")?;
+    for (code, description) in applet.manual.exit_codes {
+        write_item(out, &code.to_string(), description)?;
+    }
+
+    if !applet.manual.examples.is_empty() {
+        write_section(out, "EXAMPLES")?;
+        for (example, description) in applet.manual.examples {
+            write_item(out, example, description)?;
+        }
+    }
+
+    writeln!(out)?;
+    write_header(
+        out,
+        &format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+        &format!("{} {}", applet.name, applet.version),
+        &title,
+    )
+}
@@ -0,0 +1,968 @@
+/*
+ * Copyright (c) 2026 Antmicro <www.antmicro.com>
+ *
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! In-memory implementation of [`Kernel`] for testing applets on the host.
+//!
+//! [`MockKernel`] keeps its files, fifos, devices, processes, mounts and
+//! terminal settings in memory. Clones share the same state, so a test can
+//! pass one clone to the applet context and inspect another afterwards:
+//!
+//! ```
+//! use applet::Context;
+//! use mock_kernel::MockKernel;
+//!
+//! let kernel = MockKernel::new();
+//! let pid = kernel.add_process("/usr/bin/sleep", &["100"]);
+//!
+//! let mut ctx = Context::new(&b""[..], Vec::new(), Vec::new()).with_kernel(kernel.clone());
+//! ctx.kernel.kill(pid, applet::kernel::Signal::Kill).unwrap();
+//!
+//! assert!(!kernel.process(pid).unwrap().running);
+//! ```
+//!
+//! Besides regular files it serves the devices of the wasi kernel:
+//! `/dev/wget0` answers requests with responses added by
+//! [`MockKernel::add_http_response`], `/dev/ws0` connects to servers added by
+//! [`MockKernel::add_ws_server`] and `/proc/self/mountinfo` lists the mounts.
+//!
+//! Processes exit when killed, with the status a shell reports for the
+//! signal, or with [`MockKernel::exit`]. Their exits are returned by
+//! [`Kernel::poll_children`], with the status unless
+//! [`MockKernel::hide_exit_statuses`] was called.
+//!
+//! [`Kernel::poll`] fails with [`io::ErrorKind::WouldBlock`] when nothing can
+//! be read, so event loops return once the scripted input is consumed. Polls
+//! with a timeout advance the clock of the mock instead of waiting.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::io;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::Duration;
+
+use applet::errno;
+use applet::kernel::termios::{self, Termios};
+use applet::kernel::{
+    Device, ExitCode, Fd, Ioctl, Kernel, NameType, OpenFlags, Pid, PollEvent, Redirect, Signal,
+    WinSize, FIFO_DEV, STDIN_FD,
+};
+
+const WGET_DEVICE: &str = "/dev/wget0";
+const WGET_RESPONSE_PREFIX: &str = "/dev/wget0r";
+const WS_DEVICE: &str = "/dev/ws0";
+const WS_SOCKET_PREFIX: &str = "/dev/ws0s";
+const MOUNTINFO_PATH: &str = "/proc/self/mountinfo";
+
+// Descriptors below are taken by the standard streams
+const FIRST_FD: Fd = 3;
+// Pid 1 is init itself
+const FIRST_PID: Pid = 2;
+
+const HTTP_NOT_FOUND: i32 = 404;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Process {
+    pub pid: Pid,
+    pub path: String,
+    pub args: Vec<String>,
+    pub env: HashMap<String, String>,
+    pub redirects: Vec<Redirect>,
+    /// Signals sent with [`Kernel::kill`], in order
+    pub signals: Vec<Signal>,
+    pub running: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mount {
+    pub source: String,
+    pub target: String,
+    pub fstype: String,
+    pub data: String,
+}
+
+enum Node {
+    File(Vec<u8>),
+    // Every write is read back as a separate message, unless a fifo read
+    // size is set
+    Fifo(VecDeque<Vec<u8>>),
+    Device(i32),
+}
+
+struct HttpResponse {
+    status: i32,
+    headers: Vec<u8>,
+    body: Vec<u8>,
+}
+
+#[derive(Default)]
+struct WsServer {
+    messages: VecDeque<Vec<u8>>,
+    received: Vec<u8>,
+}
+
+#[derive(Clone)]
+enum Handle {
+    Node(String),
+    // Snapshot of a generated file, like mountinfo
+    Generated(Vec<u8>),
+    WgetControl,
+    WgetResponse(String),
+    WsControl,
+    WsSocket(String),
+}
+
+struct State {
+    nodes: BTreeMap<String, Node>,
+    // Data written to nodes before they exist
+    pending: HashMap<String, Vec<Vec<u8>>>,
+    fds: HashMap<Fd, Handle>,
+    next_fd: Fd,
+    http: HashMap<String, HttpResponse>,
+    wget_requests: Vec<String>,
+    ws: HashMap<String, WsServer>,
+    ws_connections: Vec<String>,
+    processes: Vec<Process>,
+    next_pid: Pid,
+    // Exit statuses of processes that exit right after being spawned, and
+    // the number of processes spawned first that don't
+    crashing: HashMap<String, (usize, ExitCode)>,
+    // Executables that fail to spawn
+    unspawnable: HashSet<String>,
+    // Files written, or removed if there is no content, when a process of
+    // an executable is spawned
+    spawn_writes: Vec<(String, String, Option<Vec<u8>>)>,
+    // Signals that don't make processes of an executable exit
+    ignored_signals: Vec<(String, Signal)>,
+    // Executables whose processes can't be signalled
+    unkillable: HashSet<String>,
+    // Files whose polling fails
+    unpollable: HashSet<String>,
+    // Bytes returned by a fifo read, which joins and splits the writes
+    fifo_read_size: Option<usize>,
+    // Exits not returned by poll_children yet
+    exits: VecDeque<(Pid, ExitCode)>,
+    // Report exits without a status, as the wasi kernel does
+    hide_exit_statuses: bool,
+    clock: Duration,
+    mounts: Vec<Mount>,
+    termios: Termios,
+    winsize: WinSize,
+    uname: HashMap<NameType, String>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            nodes: BTreeMap::new(),
+            pending: HashMap::new(),
+            fds: HashMap::new(),
+            next_fd: FIRST_FD,
+            http: HashMap::new(),
+            wget_requests: Vec::new(),
+            ws: HashMap::new(),
+            ws_connections: Vec::new(),
+            processes: Vec::new(),
+            next_pid: FIRST_PID,
+            crashing: HashMap::new(),
+            unspawnable: HashSet::new(),
+            spawn_writes: Vec::new(),
+            ignored_signals: Vec::new(),
+            unkillable: HashSet::new(),
+            unpollable: HashSet::new(),
+            fifo_read_size: None,
+            exits: VecDeque::new(),
+            hide_exit_statuses: false,
+            clock: Duration::ZERO,
+            mounts: vec![Mount {
+                source: String::new(),
+                target: String::from("/"),
+                fstype: String::from("fsa"),
+                data: String::new(),
+            }],
+            termios: Termios {
+                c_iflag: termios::ICRNL | termios::IXON | termios::IMAXBEL | termios::IUTF8,
+                c_oflag: termios::OPOST | termios::ONLCR,
+                c_cflag: termios::CS8 | termios::CREAD,
+                c_lflag: termios::ISIG
+                    | termios::ICANON
+                    | termios::ECHO
+                    | termios::ECHOE
+                    | termios::ECHOK
+                    | termios::IEXTEN,
+            },
+            winsize: WinSize {
+                ws_row: 24,
+                ws_col: 80,
+            },
+            uname: HashMap::from([
+                (
+                    NameType::Href,
+                    String::from("http://localhost:8000/index.html"),
+                ),
+                (NameType::Protocol, String::from("http:")),
+                (NameType::Host, String::from("localhost:8000")),
+                (NameType::Port, String::from("8000")),
+                (NameType::Pathname, String::from("/index.html")),
+                (NameType::Search, String::new()),
+                (NameType::Hash, String::new()),
+                (NameType::Origin, String::from("http://localhost:8000")),
+                (
+                    NameType::UserAgent,
+                    String::from("Mozilla/5.0 (wasibox mock)"),
+                ),
+            ]),
+        }
+    }
+}
+
+impl State {
+    fn create_node(&mut self, path: &str, node: Node) {
+        let mut node = node;
+        for data in self.pending.remove(path).unwrap_or_default() {
+            match &mut node {
+                Node::File(content) => content.extend(data),
+                Node::Fifo(messages) => messages.push_back(data),
+                Node::Device(_) => {}
+            }
+        }
+        self.nodes.insert(path.to_string(), node);
+    }
+
+    fn exit_process(&mut self, pid: Pid, status: ExitCode) {
+        if let Some(process) = self
+            .processes
+            .iter_mut()
+            .find(|process| process.pid == pid && process.running)
+        {
+            process.running = false;
+            self.exits.push_back((pid, status));
+        }
+    }
+
+    fn mountinfo(&self) -> Vec<u8> {
+        self.mounts
+            .iter()
+            .map(|m| format!("{} {} {} {}\n", m.target, m.fstype, m.source, m.data))
+            .collect::<String>()
+            .into_bytes()
+    }
+}
+
+// Like the real kernel, errors only carry the errno description and the
+// applets add the path they failed on
+fn error(kind: io::ErrorKind, errno: i32) -> io::Error {
+    io::Error::new(kind, errno::strerror(errno))
+}
+
+fn not_found() -> io::Error {
+    error(io::ErrorKind::NotFound, errno::ENOENT)
+}
+
+fn invalid() -> io::Error {
+    error(io::ErrorKind::InvalidInput, errno::EINVAL)
+}
+
+fn minor_path(prefix: &str, path: &str, count: usize) -> Option<usize> {
+    path.strip_prefix(prefix)?
+        .parse::<usize>()
+        .ok()
+        .filter(|minor| *minor < count)
+}
+
+#[derive(Clone, Copy)]
+enum WgetPart {
+    Headers,
+    Body,
+}
+
+struct MockDevice {
+    state: Rc<RefCell<State>>,
+    fd: Fd,
+    handle: Handle,
+    position: usize,
+    wget_part: Option<WgetPart>,
+}
+
+impl Read for MockDevice {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut state = self.state.borrow_mut();
+        let read_size = state.fifo_read_size;
+        let content: &[u8] = match &self.handle {
+            Handle::Node(path) => match state.nodes.get_mut(path) {
+                Some(Node::File(content)) => content,
+                Some(Node::Fifo(messages)) => {
+                    let limit = read_size.map_or(buf.len(), |size| size.min(buf.len()));
+                    let mut len = 0;
+                    while let Some(message) = messages.front_mut() {
+                        let count = (limit - len).min(message.len());
+                        buf[len..len + count].copy_from_slice(&message[..count]);
+                        message.drain(..count);
+                        len += count;
+                        if message.is_empty() {
+                            messages.pop_front();
+                        }
+                        if read_size.is_none() || len == limit {
+                            break;
+                        }
+                    }
+                    return Ok(len);
+                }
+                Some(Node::Device(_)) => return Ok(0),
+                None => return Err(not_found()),
+            },
+            Handle::Generated(content) => content,
+            Handle::WgetResponse(url) => match (state.http.get(url), self.wget_part) {
+                (Some(response), Some(WgetPart::Headers)) => &response.headers,
+                (Some(response), Some(WgetPart::Body)) => &response.body,
+                _ => &[],
+            },
+            Handle::WsSocket(url) => {
+                let server = state.ws.get_mut(url).unwrap();
+                let Some(message) = server.messages.pop_front() else {
+                    return Ok(0);
+                };
+                let len = buf.len().min(message.len());
+                buf[..len].copy_from_slice(&message[..len]);
+                return Ok(len);
+            }
+            Handle::WgetControl | Handle::WsControl => return Ok(0),
+        };
+        let rest = content.get(self.position..).unwrap_or_default();
+        let len = buf.len().min(rest.len());
+        buf[..len].copy_from_slice(&rest[..len]);
+        self.position += len;
+        Ok(len)
+    }
+}
+
+impl Write for MockDevice {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut state = self.state.borrow_mut();
+        match &self.handle {
+            Handle::Node(path) => match state.nodes.get_mut(path) {
+                Some(Node::File(content)) => content.extend_from_slice(buf),
+                Some(Node::Fifo(messages)) => messages.push_back(buf.to_vec()),
+                Some(Node::Device(_)) => {}
+                None => return Err(not_found()),
+            },
+            Handle::Generated(_) | Handle::WgetResponse(_) => {
+                return Err(error(io::ErrorKind::InvalidInput, errno::EBADF))
+            }
+            // Like the kernel, the control devices return the minor number
+            // of the new request instead of the written length
+            Handle::WgetControl => {
+                let url = String::from_utf8_lossy(buf).to_string();
+                state.wget_requests.push(url);
+                return Ok(state.wget_requests.len() - 1);
+            }
+            Handle::WsControl => {
+                let url = String::from_utf8_lossy(buf).to_string();
+                if !state.ws.contains_key(&url) {
+                    return Err(error(io::ErrorKind::ConnectionRefused, errno::ECONNREFUSED));
+                }
+                state.ws_connections.push(url);
+                return Ok(state.ws_connections.len() - 1);
+            }
+            Handle::WsSocket(url) => {
+                state
+                    .ws
+                    .get_mut(url)
+                    .unwrap()
+                    .received
+                    .extend_from_slice(buf);
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Only files can be seeked, reads and writes of other devices don't move
+impl Seek for MockDevice {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let state = self.state.borrow();
+        let len = match &self.handle {
+            Handle::Node(path) => match state.nodes.get(path) {
+                Some(Node::File(content)) => content.len(),
+                Some(_) => return Err(error(io::ErrorKind::InvalidInput, errno::ESPIPE)),
+                None => return Err(not_found()),
+            },
+            Handle::Generated(content) => content.len(),
+            _ => return Err(error(io::ErrorKind::InvalidInput, errno::ESPIPE)),
+        };
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => (len as u64).checked_add_signed(offset),
+            SeekFrom::Current(offset) => (self.position as u64).checked_add_signed(offset),
+        }
+        .ok_or_else(|| error(io::ErrorKind::InvalidInput, errno::EINVAL))?;
+        self.position = position as usize;
+        Ok(position)
+    }
+}
+
+impl Device for MockDevice {
+    fn ioctl(&mut self, request: Ioctl, arg: Option<&mut i32>) -> io::Result<()> {
+        let state = self.state.borrow_mut();
+        match (&self.handle, request) {
+            (Handle::WgetResponse(url), Ioctl::WgetStatus) => {
+                if let Some(arg) = arg {
+                    *arg = state
+                        .http
+                        .get(url)
+                        .map_or(HTTP_NOT_FOUND, |response| response.status);
+                }
+            }
+            (Handle::WgetResponse(_), Ioctl::WgetHeaders) => {
+                self.wget_part = Some(WgetPart::Headers);
+                self.position = 0;
+            }
+            (Handle::WgetResponse(_), Ioctl::WgetBody) => {
+                self.wget_part = Some(WgetPart::Body);
+                self.position = 0;
+            }
+            (
+                Handle::Node(path),
+                Ioctl::FifoKernelWriter | Ioctl::FifoKernelReader | Ioctl::FifoCloseRemove,
+            ) if matches!(state.nodes.get(path), Some(Node::Fifo(_))) => {}
+            _ => return Err(error(io::ErrorKind::InvalidInput, errno::ENOTTY)),
+        }
+        Ok(())
+    }
+
+    fn set_cloexec(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn fd(&self) -> Fd {
+        self.fd
+    }
+}
+
+impl Drop for MockDevice {
+    fn drop(&mut self) {
+        self.state.borrow_mut().fds.remove(&self.fd);
+    }
+}
+
+/// In-memory kernel, see the [crate documentation](crate).
+#[derive(Clone, Default)]
+pub struct MockKernel {
+    state: Rc<RefCell<State>>,
+}
+
+impl MockKernel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a regular file, replacing an existing one.
+    pub fn add_file<C: Into<Vec<u8>>>(&self, path: &str, content: C) {
+        self.state
+            .borrow_mut()
+            .create_node(path, Node::File(content.into()));
+    }
+
+    /// Contents of a file, or the unread messages of a fifo.
+    pub fn file(&self, path: &str) -> Option<Vec<u8>> {
+        match self.state.borrow().nodes.get(path)? {
+            Node::File(content) => Some(content.clone()),
+            Node::Fifo(messages) => Some(messages.iter().flatten().copied().collect()),
+            Node::Device(_) => None,
+        }
+    }
+
+    /// Writes to a file or fifo like another process would, waiting for
+    /// the node to be created if it doesn't exist yet.
+    pub fn feed<D: Into<Vec<u8>>>(&self, path: &str, data: D) {
+        let mut state = self.state.borrow_mut();
+        let data = data.into();
+        match state.nodes.get_mut(path) {
+            Some(Node::File(content)) => content.extend(data),
+            Some(Node::Fifo(messages)) => messages.push_back(data),
+            Some(Node::Device(_)) => {}
+            None => state
+                .pending
+                .entry(path.to_string())
+                .or_default()
+                .push(data),
+        }
+    }
+
+    pub fn is_fifo(&self, path: &str) -> bool {
+        matches!(self.state.borrow().nodes.get(path), Some(Node::Fifo(_)))
+    }
+
+    /// Device number of a device file created with [`Kernel::mknod`].
+    pub fn device(&self, path: &str) -> Option<i32> {
+        match self.state.borrow().nodes.get(path)? {
+            Node::Device(dev) => Some(*dev),
+            _ => None,
+        }
+    }
+
+    /// Makes wget requests of the url get the response.
+    pub fn add_http_response(&self, url: &str, status: i32, headers: &str, body: &[u8]) {
+        self.state.borrow_mut().http.insert(
+            url.to_string(),
+            HttpResponse {
+                status,
+                headers: headers.as_bytes().to_vec(),
+                body: body.to_vec(),
+            },
+        );
+    }
+
+    /// Urls requested through `/dev/wget0`, in order.
+    pub fn wget_requests(&self) -> Vec<String> {
+        self.state.borrow().wget_requests.clone()
+    }
+
+    /// Adds a WebSocket server that sends the messages to every new
+    /// connection and closes it once they are read.
+    pub fn add_ws_server(&self, url: &str, messages: &[&[u8]]) {
+        self.state.borrow_mut().ws.insert(
+            url.to_string(),
+            WsServer {
+                messages: messages.iter().map(|m| m.to_vec()).collect(),
+                received: Vec::new(),
+            },
+        );
+    }
+
+    /// Data sent to the WebSocket server.
+    pub fn ws_received(&self, url: &str) -> Vec<u8> {
+        self.state
+            .borrow()
+            .ws
+            .get(url)
+            .map(|server| server.received.clone())
+            .unwrap_or_default()
+    }
+
+    /// Adds a running process, as if it was spawned by someone else.
+    pub fn add_process(&self, path: &str, args: &[&str]) -> Pid {
+        self.spawn_process(path, args, &HashMap::new(), &[])
+    }
+
+    pub fn process(&self, pid: Pid) -> Option<Process> {
+        self.state
+            .borrow()
+            .processes
+            .iter()
+            .find(|process| process.pid == pid)
+            .cloned()
+    }
+
+    /// Makes a running process exit with `status`.
+    pub fn exit(&self, pid: Pid, status: ExitCode) {
+        self.state.borrow_mut().exit_process(pid, status);
+    }
+
+    /// Makes every fifo read return `size` bytes when there are enough,
+    /// joining consecutive writes and splitting them like a pipe can.
+    pub fn set_fifo_read_size(&self, size: usize) {
+        self.state.borrow_mut().fifo_read_size = Some(size);
+    }
+
+    /// Makes [`Kernel::poll_children`] report exits without their status,
+    /// like the wasi kernel does.
+    pub fn hide_exit_statuses(&self) {
+        self.state.borrow_mut().hide_exit_statuses = true;
+    }
+
+    /// Makes every process spawned from `path` from now on exit with
+    /// `status` right away, like a service crashing at startup.
+    pub fn crash_on_spawn(&self, path: &str, status: ExitCode) {
+        self.crash_on_spawn_after(path, 0, status);
+    }
+
+    /// Like [`MockKernel::crash_on_spawn`], but only once `spawned`
+    /// processes of `path` were spawned in total.
+    pub fn crash_on_spawn_after(&self, path: &str, spawned: usize, status: ExitCode) {
+        self.state
+            .borrow_mut()
+            .crashing
+            .insert(path.to_string(), (spawned, status));
+    }
+
+    /// Makes spawning processes from `path` fail, as if the executable
+    /// did not exist.
+    pub fn fail_spawn(&self, path: &str) {
+        self.state.borrow_mut().unspawnable.insert(path.to_string());
+    }
+
+    /// Makes spawning a process from `path` write `content` to `file`,
+    /// like a tool editing it, or remove the file if there is no content.
+    pub fn write_on_spawn<C: Into<Vec<u8>>>(&self, path: &str, file: &str, content: Option<C>) {
+        self.state.borrow_mut().spawn_writes.push((
+            path.to_string(),
+            file.to_string(),
+            content.map(Into::into),
+        ));
+    }
+
+    /// Makes processes of `path` keep running when they get `signal`, like
+    /// a process handling it.
+    pub fn ignore_signal(&self, path: &str, signal: Signal) {
+        self.state
+            .borrow_mut()
+            .ignored_signals
+            .push((path.to_string(), signal));
+    }
+
+    /// Makes signalling processes of `path` fail, as if they were not
+    /// permitted to be signalled.
+    pub fn fail_kill(&self, path: &str) {
+        self.state.borrow_mut().unkillable.insert(path.to_string());
+    }
+
+    /// Makes polling a file opened from `path` fail, like a poll reporting
+    /// an error for it.
+    pub fn fail_poll(&self, path: &str) {
+        self.state.borrow_mut().unpollable.insert(path.to_string());
+    }
+
+    /// Every process spawned or added so far, in order.
+    pub fn processes(&self) -> Vec<Process> {
+        self.state.borrow().processes.clone()
+    }
+
+    pub fn mounts(&self) -> Vec<Mount> {
+        self.state.borrow().mounts.clone()
+    }
+
+    pub fn termios(&self) -> Termios {
+        self.state.borrow().termios
+    }
+
+    pub fn set_termios(&self, termios: Termios) {
+        self.state.borrow_mut().termios = termios;
+    }
+
+    pub fn set_winsize(&self, rows: u16, columns: u16) {
+        self.state.borrow_mut().winsize = WinSize {
+            ws_row: rows,
+            ws_col: columns,
+        };
+    }
+
+    pub fn set_uname(&self, name: NameType, value: &str) {
+        self.state
+            .borrow_mut()
+            .uname
+            .insert(name, value.to_string());
+    }
+
+    fn spawn_process(
+        &self,
+        path: &str,
+        args: &[&str],
+        env: &HashMap<String, String>,
+        redirects: &[Redirect],
+    ) -> Pid {
+        let mut state = self.state.borrow_mut();
+        let pid = state.next_pid;
+        state.next_pid += 1;
+        let writes = state
+            .spawn_writes
+            .iter()
+            .filter(|(executable, _, _)| executable == path)
+            .map(|(_, file, content)| (file.clone(), content.clone()))
+            .collect::<Vec<_>>();
+        for (file, content) in writes {
+            match content {
+                Some(content) => state.create_node(&file, Node::File(content)),
+                None => {
+                    state.nodes.remove(&file);
+                }
+            }
+        }
+        let spawned = state
+            .processes
+            .iter()
+            .filter(|process| process.path == path)
+            .count();
+        state.processes.push(Process {
+            pid,
+            path: path.to_string(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            env: env.clone(),
+            redirects: redirects.to_vec(),
+            signals: Vec::new(),
+            running: true,
+        });
+        match state.crashing.get(path).copied() {
+            Some((after, status)) if spawned >= after => state.exit_process(pid, status),
+            _ => {}
+        }
+        pid
+    }
+}
+
+impl Kernel for MockKernel {
+    fn open(&mut self, path: &str, flags: OpenFlags) -> io::Result<Box<dyn Device>> {
+        let mut state = self.state.borrow_mut();
+        let handle = if path == WGET_DEVICE {
+            Handle::WgetControl
+        } else if path == WS_DEVICE {
+            Handle::WsControl
+        } else if path == MOUNTINFO_PATH {
+            Handle::Generated(state.mountinfo())
+        } else if let Some(minor) =
+            minor_path(WGET_RESPONSE_PREFIX, path, state.wget_requests.len())
+        {
+            Handle::WgetResponse(state.wget_requests[minor].clone())
+        } else if let Some(minor) = minor_path(WS_SOCKET_PREFIX, path, state.ws_connections.len()) {
+            Handle::WsSocket(state.ws_connections[minor].clone())
+        } else {
+            match state.nodes.get_mut(path) {
+                Some(Node::File(content)) if flags.truncate => content.clear(),
+                Some(_) => {}
+                None if flags.create => state.create_node(path, Node::File(Vec::new())),
+                None => return Err(not_found()),
+            }
+            Handle::Node(path.to_string())
+        };
+
+        let fd = state.next_fd;
+        state.next_fd += 1;
+        state.fds.insert(fd, handle.clone());
+        Ok(Box::new(MockDevice {
+            state: self.state.clone(),
+            fd,
+            handle,
+            position: 0,
+            wget_part: None,
+        }))
+    }
+
+    // Directories are not stored, so every directory exists and the ones
+    // without nodes are empty
+    fn read_dir(&mut self, path: &str) -> io::Result<Vec<PathBuf>> {
+        Ok(self
+            .state
+            .borrow()
+            .nodes
+            .keys()
+            .filter(|node| Path::new(node).parent() == Some(Path::new(path)))
+            .map(PathBuf::from)
+            .collect())
+    }
+
+    fn create_dir_all(&mut self, _path: &str) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn rename(&mut self, from: &str, to: &str) -> io::Result<()> {
+        let mut state = self.state.borrow_mut();
+        let node = state.nodes.remove(from).ok_or_else(not_found)?;
+        state.nodes.insert(to.to_string(), node);
+        Ok(())
+    }
+
+    fn remove_file(&mut self, path: &str) -> io::Result<()> {
+        let mut state = self.state.borrow_mut();
+        state.nodes.remove(path).map(|_| ()).ok_or_else(not_found)
+    }
+
+    fn poll(&mut self, fds: &[Fd], timeout: Option<Duration>) -> io::Result<Vec<PollEvent>> {
+        let mut state = self.state.borrow_mut();
+        let unpollable = fds.iter().any(|fd| {
+            matches!(state.fds.get(fd), Some(Handle::Node(path)) if state.unpollable.contains(path))
+        });
+        if unpollable {
+            return Err(error(io::ErrorKind::Other, errno::EIO));
+        }
+        let events = fds
+            .iter()
+            .filter_map(|fd| {
+                if *fd == STDIN_FD {
+                    // The mock can't see the input of the context, it's
+                    // always ready and ends with an empty read
+                    return Some(PollEvent::Readable(*fd));
+                }
+                match state.fds.get(fd)? {
+                    Handle::Node(path) => match state.nodes.get(path) {
+                        Some(Node::Fifo(messages)) if messages.is_empty() => None,
+                        Some(_) => Some(PollEvent::Readable(*fd)),
+                        None => Some(PollEvent::Hangup(*fd)),
+                    },
+                    Handle::WsSocket(url) => {
+                        if state.ws[url].messages.is_empty() {
+                            Some(PollEvent::Hangup(*fd))
+                        } else {
+                            Some(PollEvent::Readable(*fd))
+                        }
+                    }
+                    _ => Some(PollEvent::Readable(*fd)),
+                }
+            })
+            .collect::<Vec<PollEvent>>();
+
+        if let (true, Some(timeout)) = (events.is_empty(), timeout) {
+            state.clock += timeout;
+            Ok(events)
+        } else if events.is_empty() {
+            Err(io::Error::new(
+                io::ErrorKind::WouldBlock,
+                "No more events in the mock kernel",
+            ))
+        } else {
+            Ok(events)
+        }
+    }
+
+    fn poll_children(
+        &mut self,
+        fds: &[Fd],
+        children: &[Pid],
+        timeout: Option<Duration>,
+    ) -> io::Result<Vec<PollEvent>> {
+        let mut events = Vec::new();
+        {
+            let mut state = self.state.borrow_mut();
+            let hide_status = state.hide_exit_statuses;
+            state.exits.retain(|(pid, status)| {
+                if !children.contains(pid) {
+                    return true;
+                }
+                events.push(PollEvent::Exited(
+                    *pid,
+                    Some(*status).filter(|_| !hide_status),
+                ));
+                false
+            });
+        }
+        if events.is_empty() {
+            return self.poll(fds, timeout);
+        }
+        match self.poll(fds, None) {
+            Ok(others) => events.extend(others),
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+            Err(e) => return Err(e),
+        }
+        Ok(events)
+    }
+
+    fn clock(&mut self) -> io::Result<Duration> {
+        Ok(self.state.borrow().clock)
+    }
+
+    fn spawn(
+        &mut self,
+        path: &str,
+        args: &[&str],
+        env: &HashMap<String, String>,
+        _background: bool,
+        redirects: &[Redirect],
+    ) -> io::Result<(ExitCode, Pid)> {
+        if self.state.borrow().unspawnable.contains(path) {
+            return Err(not_found());
+        }
+        Ok((0, self.spawn_process(path, args, env, redirects)))
+    }
+
+    fn kill(&mut self, pid: Pid, signal: Signal) -> io::Result<()> {
+        let mut state = self.state.borrow_mut();
+        let ignored_signals = state.ignored_signals.clone();
+        let unkillable = state.unkillable.clone();
+        match state
+            .processes
+            .iter_mut()
+            .find(|process| process.pid == pid && process.running)
+        {
+            Some(process) if unkillable.contains(&process.path) => {
+                Err(error(io::ErrorKind::PermissionDenied, errno::EPERM))
+            }
+            Some(process) => {
+                process.signals.push(signal);
+                let ignored = ignored_signals.contains(&(process.path.clone(), signal));
+                // Exit statuses of the signals as reported by shells
+                let status = match signal {
+                    _ if ignored && signal != Signal::Kill => None,
+                    Signal::Kill => Some(137),
+                    Signal::Term => Some(143),
+                    Signal::Int => Some(130),
+                    _ => None,
+                };
+                if let Some(status) = status {
+                    state.exit_process(pid, status);
+                }
+                Ok(())
+            }
+            None => Err(error(io::ErrorKind::NotFound, errno::ESRCH)),
+        }
+    }
+
+    fn mknod(&mut self, path: &str, dev: i32) -> io::Result<()> {
+        let mut state = self.state.borrow_mut();
+        if state.nodes.contains_key(path) {
+            return Err(error(io::ErrorKind::AlreadyExists, errno::EEXIST));
+        }
+        let node = match dev {
+            FIFO_DEV => Node::Fifo(VecDeque::new()),
+            dev if dev >= 0 => Node::Device(dev),
+            _ => return Err(invalid()),
+        };
+        state.create_node(path, node);
+        Ok(())
+    }
+
+    fn mount(
+        &mut self,
+        source: &str,
+        target: &str,
+        fstype: &str,
+        _flags: u64,
+        data: &str,
+    ) -> io::Result<()> {
+        let mut state = self.state.borrow_mut();
+        if state.mounts.iter().any(|m| m.target == target) {
+            return Err(error(io::ErrorKind::AlreadyExists, errno::EBUSY));
+        }
+        state.mounts.push(Mount {
+            source: source.to_string(),
+            target: target.to_string(),
+            fstype: fstype.to_string(),
+            data: data.to_string(),
+        });
+        Ok(())
+    }
+
+    fn umount(&mut self, target: &str) -> io::Result<()> {
+        let mut state = self.state.borrow_mut();
+        match state.mounts.iter().position(|m| m.target == target) {
+            Some(i) => {
+                state.mounts.remove(i);
+                Ok(())
+            }
+            None => Err(invalid()),
+        }
+    }
+
+    fn uname(&mut self, name: NameType) -> io::Result<String> {
+        Ok(self.state.borrow().uname[&name].clone())
+    }
+
+    fn tcgetattr(&mut self, _fd: Fd) -> io::Result<Termios> {
+        Ok(self.state.borrow().termios)
+    }
+
+    fn tcsetattr(&mut self, _fd: Fd, termios: &Termios) -> io::Result<()> {
+        self.state.borrow_mut().termios = *termios;
+        Ok(())
+    }
+
+    fn tcgetwinsize(&mut self, _fd: Fd) -> io::Result<WinSize> {
+        Ok(self.state.borrow().winsize)
+    }
+}
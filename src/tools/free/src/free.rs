@@ -5,14 +5,24 @@
 */
 
 use clap::ArgGroup;
-use clap::Parser;
+use clap::{Command, CommandFactory, Parser};
 use std::collections::HashMap;
-use std::env::Args;
 use std::fs;
 use std::io;
+use std::io::Write;
+
+use applet::errno::WithPath;
+use applet::{parse_args, AppletError, AppletResult, Context, Manual, EXIT_FAILURE, EXIT_USAGE};
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 const MEMINFO_PATH: &str = "/proc/meminfo";
 
+/// Display the amount of free and used memory
+///
+/// Reads /proc/meminfo and prints the total, used, free and available
+/// memory. Values are in kibibytes unless another unit is selected. The
+/// memory information is only provided by Chromium-based browsers.
 #[derive(Parser)]
 #[command(no_binary_name = true, disable_help_flag = true)]
 #[command(group(
@@ -77,6 +87,23 @@ struct CliArgs {
     help: Option<bool>,
 }
 
+pub fn command() -> Command {
+    CliArgs::command()
+}
+
+pub const MANUAL: Manual = Manual {
+    exit_codes: &[
+        (0, "success"),
+        (EXIT_FAILURE, "the memory information is unavailable"),
+        (EXIT_USAGE, "invalid command line arguments"),
+    ],
+    examples: &[
+        ("free -h", "show the memory with human-readable units"),
+        ("free --mega", "show the memory in megabytes"),
+    ],
+    sections: &[],
+};
+
 fn format_to_exponent(val: u64, exponent: u32, si: bool) -> String {
     let base: u64 = if si { 10 } else { 2 };
     format!("{:11}", val / base.pow(exponent))
@@ -106,19 +133,19 @@ fn format_human(bytes: u64, si: bool) -> String {
     format!("{:>11}", val)
 }
 
-pub fn free(args: Args) -> io::Result<()> {
-    let cli = CliArgs::parse_from(args);
+pub fn free(args: impl IntoIterator<Item = impl Into<String>>, ctx: &mut Context) -> AppletResult {
+    let cli: CliArgs = parse_args(args, ctx)?;
 
-    let contents = match fs::read_to_string(MEMINFO_PATH) {
+    let contents = match fs::read_to_string(MEMINFO_PATH).with_path(MEMINFO_PATH) {
         Ok(contents) => contents,
         Err(err) => {
             if err.kind() == io::ErrorKind::NotFound {
-                return Err(io::Error::new(
-                    io::ErrorKind::Unsupported,
+                return Err(AppletError::new(
+                    EXIT_FAILURE,
                     "free works only with Chromium-based browsers",
                 ));
             } else {
-                return Err(err);
+                return Err(err.into());
             }
         }
     };
@@ -200,8 +227,15 @@ pub fn free(args: Args) -> io::Result<()> {
         )
     };
 
-    println!("               total        used        free   available");
-    println!("Mem:     {} {} {} {}", total, used, free, available);
+    writeln!(
+        ctx.stdout,
+        "               total        used        free   available"
+    )?;
+    writeln!(
+        ctx.stdout,
+        "Mem:     {} {} {} {}",
+        total, used, free, available
+    )?;
 
     Ok(())
 }
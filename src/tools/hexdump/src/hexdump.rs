@@ -4,54 +4,84 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 
-use std::env::Args;
 use std::fs;
 use std::io;
+use std::io::Write;
 
-pub fn hexdump(mut args: Args) -> io::Result<()> {
-    let contents = fs::read(match args.next() {
-        Some(f) => f,
-        None => {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidInput,
-                "hexdump: help: hexdump <filename>",
-            ));
-        }
-    })?;
+use applet::errno::WithPath;
+use applet::{parse_args, AppletResult, Context, Manual, EXIT_FAILURE, EXIT_USAGE};
+use clap::{Command, CommandFactory, Parser, ValueHint};
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Display file contents in hexadecimal
+///
+/// Every line shows the offset in the file, the next 16 bytes in hexadecimal
+/// and the same bytes as printable characters, like `hexdump -C`.
+#[derive(Parser)]
+#[command(no_binary_name = true)]
+struct CliArgs {
+    /// file to display
+    #[arg(value_hint = ValueHint::FilePath)]
+    file: String,
+}
+
+pub fn command() -> Command {
+    CliArgs::command()
+}
+
+pub const MANUAL: Manual = Manual {
+    exit_codes: &[
+        (0, "success"),
+        (EXIT_FAILURE, "the file could not be read"),
+        (EXIT_USAGE, "invalid command line arguments"),
+    ],
+    examples: &[("hexdump /usr/bin/wasibox", "show the bytes of a binary")],
+    sections: &[],
+};
+
+pub fn hexdump(
+    args: impl IntoIterator<Item = impl Into<String>>,
+    ctx: &mut Context,
+) -> AppletResult {
+    let cli: CliArgs = parse_args(args, ctx)?;
+    let contents = fs::read(&cli.file).with_path(&cli.file)?;
+    let mut out = io::BufWriter::new(&mut ctx.stdout);
     let len = contents.len();
     let mut v = ['.'; 16];
     for j in 0..len {
         let c = contents[j] as char;
         v[j % 16] = c;
         if (j % 16) == 0 {
-            print!("{:08x} ", j);
+            write!(out, "{:08x} ", j)?;
         }
         if (j % 8) == 0 {
-            print!(" ");
+            write!(out, " ")?;
         }
-        print!("{:02x} ", c as u8);
+        write!(out, "{:02x} ", c as u8)?;
         if (j + 1) == len || (j % 16) == 15 {
             let mut count = 16;
             if (j + 1) == len {
                 count = len % 16;
                 for _ in 0..(16 - (len % 16)) {
-                    print!("   ");
+                    write!(out, "   ")?;
                 }
                 if count < 8 {
-                    print!(" ");
+                    write!(out, " ")?;
                 }
             }
-            print!(" |");
+            write!(out, " |")?;
             for c in v.iter_mut().take(count) {
                 if (0x20..0x7e).contains(&(*c as u8)) {
-                    print!("{}", *c);
+                    write!(out, "{}", *c)?;
                     *c = '.';
                 } else {
-                    print!(".");
+                    write!(out, ".")?;
                 }
             }
-            println!("|");
+            writeln!(out, "|")?;
         }
     }
+    out.flush()?;
     Ok(())
 }
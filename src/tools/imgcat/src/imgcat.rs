@@ -4,21 +4,54 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 
-use std::env::Args;
-use std::io;
+use std::io::Write;
 
-pub fn imgcat(mut args: Args) -> io::Result<()> {
-    if let Some(arg) = args.next() {
-        // TODO: find out why it breaks the order of prompt
-        iterm2::File::read(arg)?
-            .width(iterm2::Dimension::Auto)
-            .height(iterm2::Dimension::Auto)
-            .preserve_aspect_ratio(true)
-            .show()
-    } else {
-        Err(io::Error::new(
-            io::ErrorKind::InvalidInput,
-            "usage: imgcat <IMAGE>",
-        ))
-    }
+use applet::errno::WithPath;
+use applet::{parse_args, AppletResult, Context, Manual, EXIT_FAILURE, EXIT_USAGE};
+use clap::{Command, CommandFactory, Parser, ValueHint};
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Display an image in the terminal
+///
+/// The image is sent with the inline image protocol of iTerm2, which the
+/// terminal sizes automatically, keeping the aspect ratio.
+#[derive(Parser)]
+#[command(no_binary_name = true)]
+struct CliArgs {
+    /// image file to display
+    #[arg(value_hint = ValueHint::FilePath)]
+    image: String,
+}
+
+pub fn command() -> Command {
+    CliArgs::command()
+}
+
+pub const MANUAL: Manual = Manual {
+    exit_codes: &[
+        (0, "success"),
+        (EXIT_FAILURE, "the image could not be read"),
+        (EXIT_USAGE, "invalid command line arguments"),
+    ],
+    examples: &[("imgcat logo.png", "show logo.png")],
+    sections: &[],
+};
+
+pub fn imgcat(
+    args: impl IntoIterator<Item = impl Into<String>>,
+    ctx: &mut Context,
+) -> AppletResult {
+    let cli: CliArgs = parse_args(args, ctx)?;
+    // iterm2 writes the image straight to the process stdout, so make
+    // sure everything written before it is already there
+    ctx.stdout.flush()?;
+    // TODO: find out why it breaks the order of prompt
+    iterm2::File::read(&cli.image)
+        .with_path(&cli.image)?
+        .width(iterm2::Dimension::Auto)
+        .height(iterm2::Dimension::Auto)
+        .preserve_aspect_ratio(true)
+        .show()?;
+    Ok(())
 }
@@ -0,0 +1,56 @@
+/*
+ * Copyright (c) 2026 Antmicro <www.antmicro.com>
+ *
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Validation of the service files, done by `init --check`.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::io;
+
+use applet::errno;
+use applet::kernel::{Kernel, OpenFlags};
+
+use crate::services::{self, Diagnostic};
+
+/// Checks every file in /etc/init.d, returning the number of services
+/// read and the problems found, in the order of the files.
+pub(crate) fn check_services(kernel: &mut dyn Kernel) -> io::Result<(usize, Vec<Diagnostic>)> {
+    let files = services::read_service_files(kernel)?;
+    let mut diagnostics = Vec::new();
+    let mut names = HashMap::<&str, &str>::new();
+    let mut count = 0;
+
+    for file in &files {
+        diagnostics.extend(file.diagnostics.iter().cloned());
+        let Some(service) = &file.service else {
+            continue;
+        };
+        count += 1;
+
+        match names.entry(&service.name) {
+            Entry::Occupied(first) => diagnostics.push(Diagnostic::warning(
+                &file.path,
+                format!(
+                    "service {} is already defined in {}",
+                    service.name,
+                    first.get()
+                ),
+            )),
+            Entry::Vacant(entry) => {
+                entry.insert(&file.path);
+            }
+        }
+        for cmd in [&service.cmd].into_iter().chain(service.stop_cmd.first()) {
+            if let Err(e) = kernel.open(cmd, OpenFlags::READ) {
+                diagnostics.push(Diagnostic::warning(
+                    &file.path,
+                    format!("executable {}: {}", cmd, errno::describe(&e)),
+                ));
+            }
+        }
+    }
+    Ok((count, diagnostics))
+}
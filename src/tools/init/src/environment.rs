@@ -0,0 +1,94 @@
+/*
+ * Copyright (c) 2026 Antmicro <www.antmicro.com>
+ *
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Environment of the processes spawned by init.
+//!
+//! Every child gets the variables of /etc/environment, overridden by those
+//! of its env file and then by its own variables. Environment files hold
+//! NAME=VALUE lines, optionally prefixed with `export`, with values that
+//! can be quoted. Blank lines and lines starting with # are skipped. The
+//! working directory is passed to the kernel as PWD, from which it sets
+//! the directory of the new process.
+
+use std::collections::HashMap;
+use std::io;
+use std::io::Read;
+
+use applet::errno::WithPath;
+use applet::kernel::{Kernel, OpenFlags};
+
+/// Variables given to every process spawned by init.
+pub(crate) const GLOBAL_ENV_PATH: &str = "/etc/environment";
+
+/// Splits a NAME=VALUE line into the name and the unquoted value.
+fn parse_assignment(line: &str) -> Option<(String, String)> {
+    let line = line.strip_prefix("export ").unwrap_or(line);
+    let (name, value) = line.split_once('=')?;
+    let name = name.trim();
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return None;
+    }
+    let value = value.trim();
+    let value = ['"', '\'']
+        .iter()
+        .find_map(|quote| value.strip_prefix(*quote)?.strip_suffix(*quote))
+        .unwrap_or(value);
+    Some((name.to_string(), value.to_string()))
+}
+
+/// Reads the variables of an environment file.
+pub(crate) fn read_env_file(
+    kernel: &mut dyn Kernel,
+    path: &str,
+) -> io::Result<HashMap<String, String>> {
+    let mut content = String::new();
+    kernel
+        .open(path, OpenFlags::READ)
+        .and_then(|mut file| file.read_to_string(&mut content))
+        .with_path(path)?;
+
+    let mut env = HashMap::new();
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (name, value) = parse_assignment(line).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}:{}: expected NAME=VALUE", path, i + 1),
+            )
+        })?;
+        env.insert(name, value);
+    }
+    Ok(env)
+}
+
+/// Builds the environment of a child from /etc/environment, if it exists,
+/// the env file, the variables and the working directory of the child.
+pub(crate) fn child_env(
+    kernel: &mut dyn Kernel,
+    env_file: Option<&str>,
+    vars: &HashMap<String, String>,
+    cwd: Option<&str>,
+) -> io::Result<HashMap<String, String>> {
+    let mut env = match read_env_file(kernel, GLOBAL_ENV_PATH) {
+        Ok(env) => env,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => HashMap::new(),
+        Err(e) => return Err(e),
+    };
+    if let Some(path) = env_file {
+        env.extend(read_env_file(kernel, path)?);
+    }
+    env.extend(
+        vars.iter()
+            .map(|(name, value)| (name.clone(), value.clone())),
+    );
+    if let Some(cwd) = cwd {
+        env.insert(String::from("PWD"), cwd.to_string());
+    }
+    Ok(env)
+}
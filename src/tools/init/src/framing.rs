@@ -0,0 +1,102 @@
+/*
+ * Copyright (c) 2026 Antmicro <www.antmicro.com>
+ *
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Framing of the messages read from the control fifos.
+//!
+//! Messages are JSON values separated by newlines. A read can end in the
+//! middle of a message or contain several of them, so the data of every
+//! fifo is buffered until a value is complete. A message is complete as
+//! soon as its value ends, which keeps writers that don't terminate their
+//! messages working. After invalid or overlong data, reading resumes at
+//! the next line.
+
+use serde_json::{Deserializer, Value};
+
+/// Longest message accepted, in bytes.
+pub(crate) const MAX_MESSAGE_SIZE: usize = 65536;
+
+pub(crate) struct MessageBuffer {
+    data: Vec<u8>,
+    /// Set when a message is dropped before the newline ending it was read,
+    /// until that newline
+    discarding: bool,
+}
+
+impl MessageBuffer {
+    pub fn new() -> Self {
+        Self {
+            data: Vec::new(),
+            discarding: false,
+        }
+    }
+
+    /// Drops the buffered data up to the end of the current line, or all of
+    /// it if the line is incomplete.
+    fn skip_line(&mut self) {
+        match self.data.iter().position(|byte| *byte == b'\n') {
+            Some(end) => {
+                self.data.drain(..=end);
+            }
+            None => self.data.clear(),
+        }
+    }
+
+    /// Adds data read from the fifo, returning the messages it completed,
+    /// or the reasons why messages were dropped.
+    pub fn push(&mut self, mut data: &[u8]) -> Vec<Result<Value, String>> {
+        let mut messages = Vec::new();
+        if self.discarding {
+            match data.iter().position(|byte| *byte == b'\n') {
+                Some(end) => {
+                    data = &data[end + 1..];
+                    self.discarding = false;
+                }
+                None => return messages,
+            }
+        }
+        self.data.extend_from_slice(data);
+
+        loop {
+            let mut values = Deserializer::from_slice(&self.data).into_iter::<Value>();
+            let value = values.next();
+            let size = values.byte_offset();
+            match value {
+                // Only whitespace is left
+                None => {
+                    self.data.clear();
+                    break;
+                }
+                Some(Ok(value)) => {
+                    self.data.drain(..size);
+                    if size > MAX_MESSAGE_SIZE {
+                        messages.push(Err(too_long()));
+                    } else {
+                        messages.push(Ok(value));
+                    }
+                }
+                Some(Err(e)) if e.is_eof() => {
+                    if self.data.len() > MAX_MESSAGE_SIZE {
+                        messages.push(Err(too_long()));
+                        self.discarding = !self.data.contains(&b'\n');
+                        self.skip_line();
+                        continue;
+                    }
+                    break;
+                }
+                Some(Err(e)) => {
+                    messages.push(Err(format!("Invalid message: {}", e)));
+                    self.discarding = !self.data.contains(&b'\n');
+                    self.skip_line();
+                }
+            }
+        }
+        messages
+    }
+}
+
+fn too_long() -> String {
+    format!("Message longer than {} bytes dropped", MAX_MESSAGE_SIZE)
+}
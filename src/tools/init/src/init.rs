@@ -4,52 +4,166 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 
-use std::collections::HashMap;
-use std::env;
-use std::fs;
 use std::io;
-use std::io::{Read, Write};
 use std::mem;
-use std::os::fd::AsRawFd;
+use std::time::{Duration, UNIX_EPOCH};
 
-use wasi_ext_lib::{ioctl, mknod, spawn, Redirect};
-
-use serde::{Deserialize, Serialize};
+use applet::errno::{self, WithPath};
+use applet::kernel::{
+    Device, ExitCode, Fd, Ioctl, Kernel, OpenFlags, Pid, PollEvent, Redirect, FIFO_DEV,
+};
+use applet::{parse_args, AppletError, AppletResult, Context, Manual, EXIT_FAILURE, EXIT_USAGE};
+use clap::{Command, CommandFactory, Parser};
 
+mod check;
+mod environment;
+mod framing;
+mod logs;
+pub mod protocol;
 mod services;
 
-const FIFO_PATH: &str = "/dev/init.fifo";
-const KERNEL_FIFO_PATH_READ: &str = "/dev/initr.kfifo";
-const KERNEL_FIFO_PATH_WRITE: &str = "/dev/initw.kfifo";
-const LOG_PATH: &str = "/tmp/init.log";
-
-const TOKEN_KFIFO: u64 = 0;
-const TOKEN_UFIFO: u64 = 1;
-
-#[derive(Deserialize, Serialize)]
-struct SpawnArgs {
-    cmd: String,
-    stdin: Option<String>,
-    stdout: Option<String>,
-    stderr: Option<String>,
-    args: Vec<String>,
-    kern: bool,
+use framing::MessageBuffer;
+use protocol::{
+    is_reply_fifo, ErrorCode, Operation, Outcome, Reply, Request, RequestError, Response, FIFO_PATH,
+};
+use serde::Deserialize;
+use serde_json::Value;
+use services::{Restart, Severity, Target};
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Start and supervise the services from /etc/init.d
+///
+/// init starts the services described in /etc/init.d in the order of their
+/// dependencies, restarts them as their policy says and serves the requests
+/// sent through its fifos, e.g. by initctl, poweroff and reboot. Exits,
+/// restarts and failed requests are appended to /tmp/init.log.
+///
+/// With --check, init only reads the service files and reports invalid
+/// ones with the position of the problem, unknown fields, services defined
+/// twice and commands that don't exist.
+#[derive(Parser)]
+#[command(no_binary_name = true)]
+struct CliArgs {
+    /// check the files in /etc/init.d and exit
+    #[arg(long)]
+    check: bool,
 }
 
-#[derive(Deserialize, Serialize)]
-enum Operation {
-    Start(String),
-    Stop(String),
-    Spawn(SpawnArgs),
+pub fn command() -> Command {
+    CliArgs::command()
 }
 
+pub const MANUAL: Manual = Manual {
+    exit_codes: &[
+        (0, "success"),
+        (
+            EXIT_FAILURE,
+            "the fifos could not be set up, init stopped on an error or --check found problems",
+        ),
+        (EXIT_USAGE, "invalid command line arguments"),
+    ],
+    examples: &[
+        (
+            "init",
+            "start the services, usually done by the kernel at boot",
+        ),
+        ("init --check", "check the service files for problems"),
+    ],
+    sections: &[
+        (
+            "SERVICES",
+            "Every file in /etc/init.d is a JSON description of a service: its \
+             \"name\", the \"cmd\" to run with its \"args\" and the files used as \
+             its \"stdin\", \"stdout\" and \"stderr\".\n\n\
+             Services and spawned processes get the variables of /etc/environment, \
+             then those of their \"env_file\" and their \"env\" object. The \
+             NAME=VALUE lines of these files can be quoted and prefixed with \
+             \"export\". \"cwd\" sets the working directory.\n\n\
+             The output of a service without \"stdout\" or \"stderr\" is captured \
+             in /var/log/<name>.log, every line prefixed with the time it was \
+             written. Once the log would grow past \"log_max_bytes\", 65536 by \
+             default, it is rotated to <name>.log.1, keeping \"log_keep\" rotated \
+             logs, 3 by default.",
+        ),
+        (
+            "DEPENDENCIES",
+            "Services listed in \"requires\" or \"after\" are started first. A \
+             service is skipped when a service it requires is missing or fails to \
+             start, and services in a dependency cycle are not started at all. A \
+             service with a \"type\" of \"oneshot\", e.g. a setup task, runs to \
+             completion before the services depending on it are started, and fails \
+             if it exits with an error.\n\n\
+             The \"target\" of a service, \"sysinit\", \"basic\" or \"default\", \
+             the default, is the stage of the boot it belongs to: every service of \
+             a target is started, and its oneshot services finished, before those \
+             of the next one. Reaching a target is logged, and so is the \
+             completion of the boot once the default target is reached.",
+        ),
+        (
+            "STOPPING AND RESTARTING",
+            "A service is stopped by running its \"stop_cmd\", or by sending it its \
+             \"stop_signal\", TERM by default. If it is still running after \
+             \"stop_timeout_ms\", 3000 by default, it is killed.\n\n\
+             A service with a \"restart\" policy of \"on-failure\" or \"always\" is \
+             restarted after \"restart_delay_ms\", doubled for every restart within \
+             \"restart_window_ms\" up to \"restart_delay_max_ms\". After \
+             \"max_restarts\" restarts within the window init gives up.\n\n\
+             Exits are found in /proc, which doesn't keep the exit status, so a \
+             simple service exiting on its own counts as failed and a oneshot \
+             service as done.",
+        ),
+        (
+            "REQUESTS",
+            "Requests are JSON operations, one per line, e.g. {\"Stop\": \"httpd\"}. \
+             The kernel writes them to /dev/initr.kfifo and is answered on \
+             /dev/initw.kfifo. Other processes, e.g. initctl, write them to \
+             /dev/init.fifo, wrapped in a request naming a fifo to reply to, e.g. \
+             {\"operation\": {\"Status\": \"httpd\"}, \"reply\": \
+             \"/dev/initctl.0.fifo\", \"id\": 1}. init writes a line of JSON with \
+             the result of the request, or the reason it failed, to that fifo. \
+             Only fifos in /dev named *.fifo are replied to, except the ones of \
+             init named init.*.fifo, and requests naming other files are \
+             rejected. A stop request is answered \
+             once the service exited.\n\n\
+             A reload request reads /etc/init.d again: new services are started, \
+             removed ones are stopped and running services whose file changed are \
+             restarted.\n\n\
+             A shutdown request, sent by poweroff and reboot, stops every service, \
+             the services depending on others first, and waits for them to exit, \
+             at most for the sum of their stop timeouts and 5 more seconds. Every \
+             filesystem except the root one is unmounted and the log is flushed, \
+             then the system is reset on reboot, or left halted.",
+        ),
+    ],
+};
+
+const KERNEL_FIFO_PATH_READ: &str = "/dev/initr.kfifo";
+const KERNEL_FIFO_PATH_WRITE: &str = "/dev/initw.kfifo";
+pub const LOG_PATH: &str = "/tmp/init.log";
+const MOUNTINFO_PATH: &str = "/proc/self/mountinfo";
+const RESET_PATH: &str = "/proc/sys/reset";
+/// Time to wait before polling again after a poll failed
+const POLL_RETRY_DELAY: Duration = Duration::from_secs(1);
+
 struct Init {
     pub(crate) service_manager: services::ServiceManager,
 
-    ufifo: Option<fs::File>,  // userspace fifo
-    kfifor: Option<fs::File>, // kernel read fifo
-    kfifow: Option<fs::File>, // kernel write fifo
-    logfile: Option<fs::File>,
+    ufifo: Option<Box<dyn Device>>,  // userspace fifo
+    kfifor: Option<Box<dyn Device>>, // kernel read fifo
+    kfifow: Option<Box<dyn Device>>, // kernel write fifo
+    logfile: Option<Box<dyn Device>>,
+
+    // Number of the current request, also naming the fifos of spawned
+    // processes
+    iteration: i32,
+    // Stop requests answered once their service stops: the service, the
+    // reply fifo and the id of the request
+    stop_waiters: Vec<(String, String, Option<u64>)>,
+    // Clock reading at which the services started to be spawned
+    boot_started: Duration,
+    // Set by a shutdown request, to reboot once every service stopped
+    shutdown: Option<bool>,
 }
 
 impl Init {
@@ -60,105 +174,113 @@ impl Init {
             kfifor: None,
             kfifow: None,
             logfile: None,
+            iteration: 0,
+            stop_waiters: Vec::new(),
+            boot_started: Duration::ZERO,
+            shutdown: None,
         }
     }
 
-    fn setup_descriptors(&mut self) -> io::Result<()> {
+    fn setup_descriptors(&mut self, kernel: &mut dyn Kernel) -> io::Result<()> {
         let mut one = 1;
 
-        mknod(FIFO_PATH, -1).map_err(io::Error::from_raw_os_error)?;
-        mknod(KERNEL_FIFO_PATH_READ, -1).map_err(io::Error::from_raw_os_error)?;
-        mknod(KERNEL_FIFO_PATH_WRITE, -1).map_err(io::Error::from_raw_os_error)?;
+        for path in [FIFO_PATH, KERNEL_FIFO_PATH_READ, KERNEL_FIFO_PATH_WRITE] {
+            kernel.mknod(path, FIFO_DEV).with_path(path)?;
+        }
 
-        self.ufifo = Some(fs::OpenOptions::new().read(true).open(FIFO_PATH)?);
+        self.ufifo = Some(
+            kernel
+                .open(FIFO_PATH, OpenFlags::READ)
+                .with_path(FIFO_PATH)?,
+        );
         self.kfifor = Some(
-            fs::OpenOptions::new()
-                .read(true)
-                .open(KERNEL_FIFO_PATH_READ)?,
+            kernel
+                .open(KERNEL_FIFO_PATH_READ, OpenFlags::READ)
+                .with_path(KERNEL_FIFO_PATH_READ)?,
         );
-        ioctl(
-            self.kfifor.as_mut().unwrap().as_raw_fd(),
-            wasi_ext_lib::FIFOSKERNW,
-            Some(&mut one),
-        )
-        .map_err(io::Error::from_raw_os_error)?;
+        self.kfifor
+            .as_mut()
+            .unwrap()
+            .ioctl(Ioctl::FifoKernelWriter, Some(&mut one))
+            .with_path(KERNEL_FIFO_PATH_READ)?;
 
         self.kfifow = Some(
-            fs::OpenOptions::new()
-                .write(true)
-                .open(KERNEL_FIFO_PATH_WRITE)?,
-        );
-        ioctl(
-            self.kfifow.as_mut().unwrap().as_raw_fd(),
-            wasi_ext_lib::FIFOSKERNR,
-            Some(&mut one),
-        )
-        .map_err(io::Error::from_raw_os_error)?;
-        self.logfile = Some(
-            fs::OpenOptions::new()
-                .create(true)
-                .write(true)
-                .truncate(true)
-                .open(LOG_PATH)?,
+            kernel
+                .open(KERNEL_FIFO_PATH_WRITE, OpenFlags::WRITE)
+                .with_path(KERNEL_FIFO_PATH_WRITE)?,
         );
+        self.kfifow
+            .as_mut()
+            .unwrap()
+            .ioctl(Ioctl::FifoKernelReader, Some(&mut one))
+            .with_path(KERNEL_FIFO_PATH_WRITE)?;
+
+        self.logfile = Some(kernel.open(LOG_PATH, logs::APPEND).with_path(LOG_PATH)?);
         [
-            self.logfile.as_ref().unwrap().as_raw_fd(),
-            self.ufifo.as_ref().unwrap().as_raw_fd(),
-            self.kfifor.as_ref().unwrap().as_raw_fd(),
-            self.kfifow.as_ref().unwrap().as_raw_fd(),
+            self.logfile.as_mut().unwrap(),
+            self.ufifo.as_mut().unwrap(),
+            self.kfifor.as_mut().unwrap(),
+            self.kfifow.as_mut().unwrap(),
         ]
-        .iter()
-        .map(|fd| -> io::Result<()> {
-            wasi_ext_lib::fcntl(
-                *fd as u32,
-                wasi_ext_lib::FcntlCommand::F_SETFD {
-                    flags: wasi_ext_lib::WASI_EXT_FDFLAG_CLOEXEC,
-                },
-            )
-            .map_err(io::Error::from_raw_os_error)?;
-            Ok(())
-        })
-        .collect::<io::Result<Vec<()>>>()?;
+        .iter_mut()
+        .try_for_each(|device| device.set_cloexec())?;
 
         Ok(())
     }
 
     fn handle_operation(
         &mut self,
+        kernel: &mut dyn Kernel,
         operation: &Operation,
         iteration: i32,
-    ) -> io::Result<Option<i32>> {
+    ) -> Result<Response, RequestError> {
+        if self.shutdown.is_some() && !matches!(operation, Operation::Status(_) | Operation::List) {
+            return Err(RequestError::new(
+                ErrorCode::ShuttingDown,
+                "The system is shutting down",
+            ));
+        }
         match operation {
             Operation::Start(name) => {
-                if let Some(service) = self.service_manager.services.get_mut(name) {
-                    if service.pid < 0 {
-                        service.spawn()?;
-                        Ok(None)
-                    } else {
-                        Ok(None) // TODO: this should be an error
-                    }
-                } else {
-                    Err(io::Error::new(
-                        io::ErrorKind::InvalidInput,
-                        format!("Service {} not found", &name),
-                    ))
+                let service = self.service_manager.get_mut(name)?;
+                if service.state.pid.is_some() {
+                    return Err(RequestError::new(
+                        ErrorCode::AlreadyRunning,
+                        format!("Service {} is already running", name),
+                    ));
                 }
+                service.spawn(kernel)?;
+                Ok(Response::Done)
             }
             Operation::Stop(name) => {
-                if let Some(service) = self.service_manager.services.get(name) {
-                    if service.pid > 0 {
-                        wasi_ext_lib::kill(service.pid, wasi::SIGNAL_KILL)
-                            .map_err(io::Error::from_raw_os_error)?;
-                        Ok(None)
-                    } else {
-                        Ok(None) // TODO: this should be an error
-                    }
+                let now = kernel.clock()?;
+                let service = self.service_manager.get_mut(name)?;
+                // Stopping a service waiting for a restart or for its
+                // dependencies cancels it
+                if service.state.pid.is_none()
+                    && service.state.restart_at.is_none()
+                    && !service.state.waiting
+                {
+                    return Err(RequestError::new(
+                        ErrorCode::NotRunning,
+                        format!("Service {} is not running", name),
+                    ));
+                }
+                service.stop(kernel, now)?;
+                Ok(Response::Done)
+            }
+            Operation::Restart(name) => {
+                let now = kernel.clock()?;
+                let service = self.service_manager.get_mut(name)?;
+                if service.state.pid.is_some() {
+                    // Spawned again when the exit is handled
+                    service.state.restart_requested = true;
+                    service.stop(kernel, now)?;
                 } else {
-                    Err(io::Error::new(
-                        io::ErrorKind::InvalidInput,
-                        format!("Service {} not found", &name),
-                    ))
+                    service.state.restart_at = None;
+                    service.spawn(kernel)?;
                 }
+                Ok(Response::Done)
             }
             Operation::Spawn(spawn_args) => {
                 let paths = [
@@ -183,136 +305,584 @@ impl Init {
                         if override_.is_some() {
                             Ok(override_.as_ref().unwrap())
                         } else {
-                            mknod(path, -1).map_err(io::Error::from_raw_os_error)?;
+                            kernel.mknod(path, FIFO_DEV).with_path(path)?;
                             let mut one = 1;
-                            let dev = fs::OpenOptions::new().open(path)?;
-                            ioctl(dev.as_raw_fd(), wasi_ext_lib::FIFOSCLOSERM, Some(&mut one))
-                                .map_err(io::Error::from_raw_os_error)?;
+                            let mut dev =
+                                kernel.open(path, OpenFlags::default()).with_path(path)?;
+                            dev.ioctl(Ioctl::FifoCloseRemove, Some(&mut one))
+                                .with_path(path)?;
                             if spawn_args.kern {
-                                ioctl(
-                                    dev.as_raw_fd(),
+                                dev.ioctl(
                                     if i == 0 {
-                                        wasi_ext_lib::FIFOSKERNW
+                                        Ioctl::FifoKernelWriter
                                     } else {
-                                        wasi_ext_lib::FIFOSKERNR
+                                        Ioctl::FifoKernelReader
                                     },
                                     Some(&mut one),
                                 )
-                                .map_err(io::Error::from_raw_os_error)?;
+                                .with_path(path)?;
                             }
                             Ok(path)
                         }
                     })
                     .collect::<io::Result<Vec<&str>>>()?;
 
-                let (_, pid) = spawn(
-                    &spawn_args.cmd,
-                    &spawn_args
-                        .args
-                        .iter()
-                        .map(|arg| arg.as_str())
-                        .collect::<Vec<&str>>(),
-                    &HashMap::new(),
-                    true,
-                    &[
-                        Redirect::Read(0, redirect_paths[0].to_string()),
-                        Redirect::Append(1, redirect_paths[1].to_string()),
-                        Redirect::Append(2, redirect_paths[2].to_string()),
-                    ],
-                )
-                .map_err(io::Error::from_raw_os_error)?;
-
-                Ok(Some(pid))
+                let env = environment::child_env(
+                    kernel,
+                    spawn_args.env_file.as_deref(),
+                    &spawn_args.env,
+                    spawn_args.cwd.as_deref(),
+                )?;
+                let (_, pid) = kernel
+                    .spawn(
+                        &spawn_args.cmd,
+                        &spawn_args
+                            .args
+                            .iter()
+                            .map(|arg| arg.as_str())
+                            .collect::<Vec<&str>>(),
+                        &env,
+                        true,
+                        &[
+                            Redirect::Read(0, redirect_paths[0].to_string()),
+                            Redirect::Append(1, redirect_paths[1].to_string()),
+                            Redirect::Append(2, redirect_paths[2].to_string()),
+                        ],
+                    )
+                    .with_path(&spawn_args.cmd)?;
+
+                Ok(Response::Pid(pid))
+            }
+            Operation::Status(name) => {
+                let now = kernel.clock()?;
+                let service = self.service_manager.get_mut(name)?;
+                Ok(Response::Status(service.status(now)))
+            }
+            Operation::List => {
+                let statuses = self.service_manager.list(kernel.clock()?);
+                Ok(Response::List(statuses))
+            }
+            Operation::Reload => {
+                let now = kernel.clock()?;
+                let (summary, reports) = self.service_manager.reload(kernel, now)?;
+                for (action, names) in [
+                    ("Added", &summary.added),
+                    ("Removed", &summary.removed),
+                    ("Changed", &summary.changed),
+                ] {
+                    for name in names {
+                        self.log(&format!("{} {}", action, name))?;
+                    }
+                }
+                for report in reports {
+                    self.log(&report)?;
+                }
+                Ok(Response::Reloaded(summary))
+            }
+            Operation::Shutdown { reboot } => {
+                self.log(if *reboot {
+                    "Rebooting the system"
+                } else {
+                    "Powering off the system"
+                })?;
+                self.shutdown = Some(*reboot);
+                self.service_manager.begin_shutdown(kernel.clock()?);
+                Ok(Response::Done)
             }
         }
     }
 
-    fn main_loop(&mut self) -> io::Result<()> {
-        let mut buf = [0u8; 8192];
-        let subs = [
-            wasi::Subscription {
-                userdata: TOKEN_UFIFO,
-                u: wasi::SubscriptionU {
-                    tag: wasi::EVENTTYPE_FD_READ.raw(),
-                    u: wasi::SubscriptionUU {
-                        fd_read: wasi::SubscriptionFdReadwrite {
-                            file_descriptor: self.ufifo.as_ref().unwrap().as_raw_fd() as u32,
-                        },
-                    },
-                },
-            },
-            wasi::Subscription {
-                userdata: TOKEN_KFIFO,
-                u: wasi::SubscriptionU {
-                    tag: wasi::EVENTTYPE_FD_READ.raw(),
-                    u: wasi::SubscriptionUU {
-                        fd_read: wasi::SubscriptionFdReadwrite {
-                            file_descriptor: self.kfifor.as_ref().unwrap().as_raw_fd() as u32,
-                        },
-                    },
-                },
-            },
-        ];
-        let mut events: [wasi::Event; 2] = unsafe { mem::zeroed() };
-
-        let mut iteration = 0;
-        loop {
-            let count =
-                unsafe { wasi::poll_oneoff(subs.as_ptr(), events.as_mut_ptr(), subs.len()) }
-                    .map_err(|e| io::Error::from_raw_os_error(e.raw() as i32))?;
+    fn log(&mut self, message: &str) -> io::Result<()> {
+        self.logfile
+            .as_mut()
+            .unwrap()
+            .write_all(format!("{}\n", message).as_bytes())
+    }
 
-            if count == 0 {
-                continue;
+    fn run_operation(
+        &mut self,
+        kernel: &mut dyn Kernel,
+        operation: &Operation,
+    ) -> io::Result<Outcome> {
+        let result = self.handle_operation(kernel, operation, self.iteration);
+        self.iteration += 1;
+        match result {
+            Ok(response) => Ok(Outcome::Ok(response)),
+            Err(e) => {
+                self.log(&format!("Request failed: {}", e))?;
+                Ok(Outcome::Error(e))
             }
+        }
+    }
 
-            for event in events[0..count].iter() {
-                let size = if event.userdata == TOKEN_KFIFO {
-                    self.kfifor.as_mut().unwrap()
-                } else {
-                    self.ufifo.as_mut().unwrap()
-                }
-                .read(&mut buf)?;
+    // The kernel is answered with the number of the request, followed by
+    // the result of the operation if there is one
+    fn handle_kernel_request(&mut self, kernel: &mut dyn Kernel, value: Value) -> io::Result<()> {
+        let operation = match Operation::deserialize(&value) {
+            Ok(operation) => operation,
+            Err(e) => return self.log(&format!("Invalid request: {}", e)),
+        };
+        let iteration = self.iteration;
+        let result = match self.run_operation(kernel, &operation)? {
+            Outcome::Ok(Response::Done) => String::new(),
+            Outcome::Ok(Response::Pid(pid)) => format!(" {}", pid),
+            Outcome::Ok(Response::Status(status)) => {
+                format!(" {}", serde_json::to_string(&status)?)
+            }
+            Outcome::Ok(Response::List(statuses)) => {
+                format!(" {}", serde_json::to_string(&statuses)?)
+            }
+            Outcome::Ok(Response::Reloaded(summary)) => {
+                format!(" {}", serde_json::to_string(&summary)?)
+            }
+            Outcome::Error(_) => return Ok(()),
+        };
+        let _ = self
+            .kfifow
+            .as_mut()
+            .unwrap()
+            .write(format!("{}{}\n", iteration, result).as_bytes());
+        Ok(())
+    }
+
+    // Userspace processes send plain operations, or requests naming a fifo
+    // for the reply
+    fn handle_user_request(&mut self, kernel: &mut dyn Kernel, value: Value) -> io::Result<()> {
+        let request = if value.get("operation").is_some() {
+            Request::deserialize(&value)
+        } else {
+            Operation::deserialize(&value).map(|operation| Request {
+                operation,
+                reply: None,
+                id: None,
+            })
+        };
+
+        let (path, reply, stopping) = match request {
+            Ok(Request {
+                reply: Some(path), ..
+            }) if !is_reply_fifo(&path) => {
+                // The error can't be sent to a path init doesn't write to
+                self.log(&format!(
+                    "Request rejected: {}: Not a fifo in /dev to reply to",
+                    path
+                ))?;
+                return Ok(());
+            }
+            Ok(request) => {
+                let outcome = self.run_operation(kernel, &request.operation)?;
+                // Stop requests are answered once the service stops
+                let stopping = match (&request.operation, &outcome) {
+                    (Operation::Stop(name), Outcome::Ok(_))
+                        if self.service_manager.services[name].state.pid.is_some() =>
+                    {
+                        Some(name.clone())
+                    }
+                    _ => None,
+                };
+                let reply = Reply {
+                    id: request.id,
+                    outcome,
+                };
+                (request.reply, reply, stopping)
+            }
+            Err(e) => {
+                self.log(&format!("Invalid request: {}", e))?;
+                // Invalid requests are answered too if they name the fifo
+                let reply = Reply {
+                    id: value.get("id").and_then(Value::as_u64),
+                    outcome: Outcome::Error(RequestError::new(
+                        ErrorCode::InvalidRequest,
+                        e.to_string(),
+                    )),
+                };
+                let path = value
+                    .get("reply")
+                    .and_then(Value::as_str)
+                    .filter(|path| is_reply_fifo(path))
+                    .map(String::from);
+                (path, reply, None)
+            }
+        };
+        let Some(path) = path else {
+            return Ok(());
+        };
+        match stopping {
+            Some(name) => self.stop_waiters.push((name, path, reply.id)),
+            None => self.send_reply(kernel, &path, &reply)?,
+        }
+        Ok(())
+    }
+
+    fn send_reply(&mut self, kernel: &mut dyn Kernel, path: &str, reply: &Reply) -> io::Result<()> {
+        let line = serde_json::to_string(reply)? + "\n";
+        let result = kernel
+            .open(path, OpenFlags::WRITE)
+            .and_then(|mut fifo| fifo.write_all(line.as_bytes()))
+            .with_path(path);
+        if let Err(e) = result {
+            self.log(&format!("Could not reply: {}", errno::describe(&e)))?;
+        }
+        Ok(())
+    }
+
+    fn report_stop(&mut self, kernel: &mut dyn Kernel, name: &str) -> io::Result<()> {
+        self.log(&format!("{} stopped", name))?;
+        let (waiters, others) = mem::take(&mut self.stop_waiters)
+            .into_iter()
+            .partition(|(service, _, _)| service == name);
+        self.stop_waiters = others;
+        for (_, path, id) in waiters {
+            let reply = Reply {
+                id,
+                outcome: Outcome::Ok(Response::Done),
+            };
+            self.send_reply(kernel, &path, &reply)?;
+        }
+        Ok(())
+    }
+
+    fn log_restart(&mut self, name: &str, restart: Restart) -> io::Result<()> {
+        match restart {
+            Restart::No => Ok(()),
+            Restart::After(delay) => self.log(&format!(
+                "{} will be restarted in {} ms",
+                name,
+                delay.as_millis()
+            )),
+            Restart::GiveUp => {
+                self.log(&format!("{} was restarted too many times, giving up", name))
+            }
+        }
+    }
+
+    fn handle_exit(
+        &mut self,
+        kernel: &mut dyn Kernel,
+        pid: Pid,
+        status: Option<ExitCode>,
+        now: Duration,
+    ) -> io::Result<()> {
+        let Some((service, restart)) = self.service_manager.handle_exit(pid, status, now) else {
+            return Ok(());
+        };
+        let exit = service.state.last_exit.unwrap();
+        let status = match exit.status {
+            Some(status) => format!(" with status {}", status),
+            None => String::new(),
+        };
+        let message = format!(
+            "{} exited{} at {}",
+            service.name,
+            status,
+            exit.time
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+        );
+        let name = service.name.clone();
+        self.log(&message)?;
+        self.flush_log(kernel, &name)?;
+        if exit.stopped {
+            self.report_stop(kernel, &name)?;
+        }
+        self.log_restart(&name, restart)?;
+        self.service_manager.forget_removed();
+        Ok(())
+    }
+
+    // Logs what the exited process of a service left in its log fifo
+    fn flush_log(&mut self, kernel: &mut dyn Kernel, name: &str) -> io::Result<()> {
+        let Some(log) = self.service_manager.logs.get_mut(name) else {
+            return Ok(());
+        };
+        if let Err(e) = log.flush(kernel) {
+            self.log(&format!(
+                "Could not write the log of {}: {}",
+                name,
+                errno::describe(&e)
+            ))?;
+        }
+        Ok(())
+    }
+
+    // Logs the output of the service reading from `fd`, returning false if
+    // it isn't the fifo of a service log
+    fn read_log(&mut self, kernel: &mut dyn Kernel, fd: Fd) -> io::Result<bool> {
+        let Some((name, log)) = self
+            .service_manager
+            .logs
+            .iter_mut()
+            .find(|(_, log)| log.fd() == fd)
+        else {
+            return Ok(false);
+        };
+        if let Err(e) = log.read(kernel) {
+            let message = format!(
+                "Could not write the log of {}: {}",
+                name,
+                errno::describe(&e)
+            );
+            self.log(&message)?;
+            self.drop_broken_logs(kernel)?;
+        }
+        Ok(true)
+    }
+
+    // Stops capturing the output of the services whose log fifo fails to
+    // be polled, which would make every poll of init fail, returning
+    // whether there were any
+    fn drop_broken_logs(&mut self, kernel: &mut dyn Kernel) -> io::Result<bool> {
+        let broken = self
+            .service_manager
+            .logs
+            .iter()
+            .filter_map(|(name, log)| {
+                let e = kernel.poll(&[log.fd()], Some(Duration::ZERO)).err()?;
+                Some((name.clone(), e))
+            })
+            .collect::<Vec<(String, io::Error)>>();
+        for (name, e) in &broken {
+            self.service_manager.logs.remove(name);
+            self.log(&format!(
+                "Could not read the output of {}, no longer capturing it: {}",
+                name,
+                errno::describe(e)
+            ))?;
+        }
+        Ok(!broken.is_empty())
+    }
+
+    fn kill_overdue(&mut self, kernel: &mut dyn Kernel) -> io::Result<()> {
+        let now = kernel.clock()?;
+        for (name, result) in self.service_manager.kill_overdue(kernel, now) {
+            let timeout = self.service_manager.services[&name].stop_timeout_ms;
+            self.log(&format!(
+                "{} did not stop within {} ms, killing it",
+                name, timeout
+            ))?;
+            if let Err(e) = result {
+                self.log(&format!("Could not kill {}: {}", name, errno::describe(&e)))?;
+            }
+        }
+        Ok(())
+    }
 
-                let operation = match serde_json::from_slice(&buf[..size]) {
-                    Ok(v) => v,
+    fn restart_services(&mut self, kernel: &mut dyn Kernel) -> io::Result<()> {
+        let now = kernel.clock()?;
+        for (name, result, restart) in self.service_manager.restart_due(kernel, now) {
+            match result {
+                Ok(()) => self.log(&format!("Restarted {}", name))?,
+                Err(e) => self.log(&format!(
+                    "Could not restart {}: {}",
+                    name,
+                    errno::describe(&e)
+                ))?,
+            }
+            self.log_restart(&name, restart)?;
+        }
+        Ok(())
+    }
+
+    // Spawns the waiting services whose dependencies are ready, and
+    // reports the boot targets reached
+    fn start_waiting(&mut self, kernel: &mut dyn Kernel) -> io::Result<()> {
+        for report in self.service_manager.start_waiting(kernel) {
+            self.log(&report)?;
+        }
+        for target in self.service_manager.reach_targets() {
+            self.log(&format!("Reached target {}", target))?;
+            if target == Target::Default {
+                let elapsed = kernel.clock()?.saturating_sub(self.boot_started);
+                self.log(&format!("Boot completed in {} ms", elapsed.as_millis()))?;
+            }
+        }
+        Ok(())
+    }
+
+    // Stops the services whose dependents exited, returning whether every
+    // service exited
+    fn stop_for_shutdown(&mut self, kernel: &mut dyn Kernel) -> io::Result<bool> {
+        let now = kernel.clock()?;
+        let (stopped, reports) = self.service_manager.stop_for_shutdown(kernel, now);
+        for report in reports {
+            self.log(&report)?;
+        }
+        Ok(stopped)
+    }
+
+    // Unmounts every filesystem except the root one, the ones mounted last
+    // first as they can be mounted inside the others
+    fn unmount_all(&mut self, kernel: &mut dyn Kernel) -> io::Result<()> {
+        let mut mountinfo = String::new();
+        let result = kernel
+            .open(MOUNTINFO_PATH, OpenFlags::READ)
+            .and_then(|mut file| file.read_to_string(&mut mountinfo))
+            .with_path(MOUNTINFO_PATH);
+        if let Err(e) = result {
+            return self.log(&format!("Could not unmount the filesystems: {}", e));
+        }
+        let mount_points = mountinfo
+            .lines()
+            .filter_map(|line| line.split(' ').next())
+            .filter(|mount_point| *mount_point != "/");
+        for mount_point in mount_points.rev() {
+            match kernel.umount(mount_point) {
+                Ok(()) => self.log(&format!("Unmounted {}", mount_point))?,
+                Err(e) => self.log(&format!(
+                    "Could not unmount {}: {}",
+                    mount_point,
+                    errno::describe(&e)
+                ))?,
+            }
+        }
+        Ok(())
+    }
+
+    // Once every service stopped, unmounts the filesystems and flushes the
+    // log, then resets the system or leaves it halted
+    fn finish_shutdown(&mut self, kernel: &mut dyn Kernel, reboot: bool) -> io::Result<()> {
+        self.unmount_all(kernel)?;
+        self.log(if reboot {
+            "Resetting the system"
+        } else {
+            "System halted"
+        })?;
+        self.logfile.as_mut().unwrap().flush()?;
+        if reboot {
+            kernel
+                .open(RESET_PATH, OpenFlags::WRITE)
+                .and_then(|mut reset| reset.write_all(b"1"))
+                .with_path(RESET_PATH)?;
+        }
+        Ok(())
+    }
+
+    fn main_loop(&mut self, kernel: &mut dyn Kernel) -> io::Result<()> {
+        let mut buf = [0u8; 8192];
+        let ufifo = self.ufifo.as_ref().unwrap().fd();
+        let kfifo = self.kfifor.as_ref().unwrap().fd();
+        let mut umessages = MessageBuffer::new();
+        let mut kmessages = MessageBuffer::new();
+
+        loop {
+            match self.shutdown {
+                Some(reboot) => {
+                    if self.stop_for_shutdown(kernel)? {
+                        return self.finish_shutdown(kernel, reboot);
+                    }
+                }
+                None => self.start_waiting(kernel)?,
+            }
+            // Wake up for the next restart or kill
+            let timeout = match self.service_manager.next_deadline() {
+                Some(at) => Some(at.saturating_sub(kernel.clock()?)),
+                None => None,
+            };
+            let mut fds = vec![ufifo, kfifo];
+            fds.extend(self.service_manager.logs.values().map(|log| log.fd()));
+            let children = self.service_manager.pids();
+            let events = match kernel.poll_children(&fds, &children, timeout) {
+                Ok(events) => events,
+                // Nothing is left to wait for
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Err(e),
+                Err(e) => {
+                    self.log(&format!("Could not poll: {}", errno::describe(&e)))?;
+                    if !self.drop_broken_logs(kernel)? {
+                        kernel.poll(&[], Some(POLL_RETRY_DELAY))?;
+                    }
+                    Vec::new()
+                }
+            };
+            // Exits come first, so that requests see the current state
+            for event in events {
+                let fd = match event {
+                    PollEvent::Readable(fd) | PollEvent::Hangup(fd) => fd,
+                    PollEvent::Exited(pid, status) => {
+                        let now = kernel.clock()?;
+                        self.handle_exit(kernel, pid, status, now)?;
+                        continue;
+                    }
+                };
+                if self.read_log(kernel, fd)? {
+                    continue;
+                }
+                let (fifo, messages) = if fd == kfifo {
+                    (self.kfifor.as_mut().unwrap(), &mut kmessages)
+                } else {
+                    (self.ufifo.as_mut().unwrap(), &mut umessages)
+                };
+                let size = match fifo.read(&mut buf) {
+                    Ok(size) => size,
                     Err(e) => {
-                        _ = self
-                            .logfile
-                            .as_mut()
-                            .unwrap()
-                            .write(format!("{:?}\n", e).as_bytes())?;
+                        self.log(&format!("Could not read requests: {}", errno::describe(&e)))?;
                         continue;
                     }
                 };
 
-                if event.userdata == TOKEN_KFIFO {
-                    if let Ok(p) = self.handle_operation(&operation, iteration) {
-                        if let Some(pid) = p {
-                            let _ = self
-                                .kfifow
-                                .as_mut()
-                                .unwrap()
-                                .write(format!("{} {}\n", iteration, pid).as_bytes());
-                        } else {
-                            let _ = self
-                                .kfifow
-                                .as_mut()
-                                .unwrap()
-                                .write(format!("{}\n", iteration).as_bytes());
-                        }
+                for message in messages.push(&buf[..size]) {
+                    match message {
+                        Ok(value) if fd == kfifo => self.handle_kernel_request(kernel, value)?,
+                        Ok(value) => self.handle_user_request(kernel, value)?,
+                        Err(e) => self.log(&e)?,
                     }
                 }
-                iteration += 1;
             }
+            self.kill_overdue(kernel)?;
+            self.restart_services(kernel)?;
         }
     }
 }
 
-pub fn init(_args: env::Args) -> io::Result<()> {
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("{} {}", count, noun)
+    } else {
+        format!("{} {}s", count, noun)
+    }
+}
+
+fn check(ctx: &mut Context) -> AppletResult {
+    let (count, diagnostics) = check::check_services(ctx.kernel.as_mut())?;
+    for diagnostic in &diagnostics {
+        writeln!(ctx.stdout, "{}", diagnostic)?;
+    }
+    let errors = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity == Severity::Error)
+        .count();
+    writeln!(
+        ctx.stdout,
+        "{} checked, {}, {}",
+        plural(count, "service"),
+        plural(errors, "error"),
+        plural(diagnostics.len() - errors, "warning")
+    )?;
+    if diagnostics.is_empty() {
+        Ok(())
+    } else {
+        Err(AppletError::exit(EXIT_FAILURE))
+    }
+}
+
+pub fn init(args: impl IntoIterator<Item = impl Into<String>>, ctx: &mut Context) -> AppletResult {
+    let cli: CliArgs = parse_args(args, ctx)?;
+    if cli.check {
+        return check(ctx);
+    }
+    let kernel = ctx.kernel.as_mut();
     let mut init = Init::new();
-    init.setup_descriptors()?;
-    init.service_manager.load_services()?;
-    init.service_manager.spawn_services()?;
-    init.main_loop()?;
+    init.setup_descriptors(kernel)?;
+    for diagnostic in init.service_manager.load_services(kernel)? {
+        init.log(&diagnostic.to_string())?;
+    }
+    for report in init.service_manager.open_logs(kernel) {
+        init.log(&report)?;
+    }
+    init.boot_started = kernel.clock()?;
+    for report in init.service_manager.spawn_services(kernel) {
+        init.log(&report)?;
+    }
+    init.main_loop(kernel)?;
     Ok(())
 }
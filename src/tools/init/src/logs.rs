@@ -0,0 +1,222 @@
+/*
+ * Copyright (c) 2026 Antmicro <www.antmicro.com>
+ *
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Output of the services captured by init.
+//!
+//! Standard streams that a service doesn't redirect to a file are written
+//! to a fifo read by init, which appends every line to the log of the
+//! service in /var/log, prefixed with the time it was read. Once the log
+//! grows past its size limit, it is renamed to <name>.log.1, shifting the
+//! older logs, of which only the configured number is kept.
+
+use std::io;
+use std::io::{Read, Write};
+use std::mem;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use applet::errno::WithPath;
+use applet::kernel::{Device, Fd, Kernel, OpenFlags, FIFO_DEV};
+
+pub(crate) const LOG_DIR: &str = "/var/log";
+
+/// Longest line written as is, longer ones are split.
+const MAX_LINE_SIZE: usize = 4096;
+
+/// Flags of the logs, which keep the entries of the previous boots.
+pub(crate) const APPEND: OpenFlags = OpenFlags {
+    read: false,
+    write: true,
+    create: true,
+    truncate: false,
+    append: true,
+};
+
+pub(crate) fn log_path(name: &str) -> String {
+    format!("{}/{}.log", LOG_DIR, name)
+}
+
+/// Fifo the captured streams of a service are redirected to.
+pub(crate) fn fifo_path(name: &str) -> String {
+    format!("/dev/init.{}.log.fifo", name)
+}
+
+/// Formats a time as in RFC 3339, in UTC with milliseconds, e.g.
+/// 2026-01-31T12:00:00.250Z.
+pub(crate) fn format_timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = since_epoch.as_secs();
+    let (days, seconds) = (seconds / 86400, seconds % 86400);
+
+    // Converts days since the epoch to the civil date, treating years as
+    // starting in March so that leap days end them
+    let days = days as i64 + 719468;
+    let era = days / 146097;
+    let day_of_era = days % 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+pub(crate) struct ServiceLog {
+    path: String,
+    fifo: Box<dyn Device>,
+    file: Option<Box<dyn Device>>,
+    /// Size of the log file
+    size: u64,
+    pub(crate) max_size: u64,
+    /// Rotated logs kept
+    pub(crate) keep: usize,
+    /// Data after the last complete line
+    partial: Vec<u8>,
+}
+
+impl ServiceLog {
+    /// Creates the fifo of a service and opens its log, appending to the
+    /// existing one.
+    pub fn open(
+        kernel: &mut dyn Kernel,
+        name: &str,
+        max_size: u64,
+        keep: usize,
+    ) -> io::Result<Self> {
+        let fifo_path = fifo_path(name);
+        match kernel.mknod(&fifo_path, FIFO_DEV) {
+            Err(e) if e.kind() != io::ErrorKind::AlreadyExists => {
+                return Err(e).with_path(&fifo_path);
+            }
+            _ => {}
+        }
+        let mut fifo = kernel
+            .open(&fifo_path, OpenFlags::READ)
+            .with_path(&fifo_path)?;
+        fifo.set_cloexec().with_path(&fifo_path)?;
+
+        kernel.create_dir_all(LOG_DIR).with_path(LOG_DIR)?;
+        let path = log_path(name);
+        let size = match kernel.open(&path, OpenFlags::READ) {
+            Ok(mut file) => io::copy(&mut file, &mut io::sink()).with_path(&path)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => 0,
+            Err(e) => return Err(e).with_path(&path),
+        };
+        Ok(Self {
+            path,
+            fifo,
+            file: None,
+            size,
+            max_size,
+            keep,
+            partial: Vec::new(),
+        })
+    }
+
+    pub fn fd(&self) -> Fd {
+        self.fifo.fd()
+    }
+
+    /// Logs the lines of the output waiting in the fifo, returning the
+    /// size read. An incomplete line is kept until the rest of it is read.
+    pub fn read(&mut self, kernel: &mut dyn Kernel) -> io::Result<usize> {
+        let mut buf = [0u8; 4096];
+        let size = self.fifo.read(&mut buf)?;
+        let now = SystemTime::now();
+        self.partial.extend_from_slice(&buf[..size]);
+
+        let mut start = 0;
+        while let Some(end) = self.partial[start..].iter().position(|byte| *byte == b'\n') {
+            let line = self.partial[start..start + end].to_vec();
+            self.write_line(kernel, &line, now)?;
+            start += end + 1;
+        }
+        self.partial.drain(..start);
+        while self.partial.len() >= MAX_LINE_SIZE {
+            let line = self.partial.drain(..MAX_LINE_SIZE).collect::<Vec<u8>>();
+            self.write_line(kernel, &line, now)?;
+        }
+        Ok(size)
+    }
+
+    /// Logs the output left in the fifo and the incomplete line, once the
+    /// process of the service exited.
+    pub fn flush(&mut self, kernel: &mut dyn Kernel) -> io::Result<()> {
+        let fd = self.fd();
+        while !kernel.poll(&[fd], Some(Duration::ZERO))?.is_empty() {
+            if self.read(kernel)? == 0 {
+                break;
+            }
+        }
+        if !self.partial.is_empty() {
+            let line = mem::take(&mut self.partial);
+            self.write_line(kernel, &line, SystemTime::now())?;
+        }
+        Ok(())
+    }
+
+    fn write_line(
+        &mut self,
+        kernel: &mut dyn Kernel,
+        line: &[u8],
+        time: SystemTime,
+    ) -> io::Result<()> {
+        let mut entry = format!("{} ", format_timestamp(time)).into_bytes();
+        entry.extend_from_slice(line);
+        entry.push(b'\n');
+
+        if self.size > 0 && self.size + entry.len() as u64 > self.max_size {
+            self.rotate(kernel)?;
+        }
+        let file = match &mut self.file {
+            Some(file) => file,
+            None => self
+                .file
+                .insert(kernel.open(&self.path, APPEND).with_path(&self.path)?),
+        };
+        file.write_all(&entry).with_path(&self.path)?;
+        self.size += entry.len() as u64;
+        Ok(())
+    }
+
+    /// Moves the log to <name>.log.1, after moving the older logs one
+    /// place further, and starts a new one.
+    fn rotate(&mut self, kernel: &mut dyn Kernel) -> io::Result<()> {
+        self.file = None;
+        let rotated = |n: usize| format!("{}.{}", self.path, n);
+        let result = if self.keep == 0 {
+            kernel.remove_file(&self.path)
+        } else {
+            for n in (1..self.keep).rev() {
+                match kernel.rename(&rotated(n), &rotated(n + 1)) {
+                    Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                        return Err(e).with_path(rotated(n));
+                    }
+                    _ => {}
+                }
+            }
+            kernel.rename(&self.path, &rotated(1))
+        };
+        match result {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e).with_path(&self.path),
+            _ => {
+                self.size = 0;
+                Ok(())
+            }
+        }
+    }
+}
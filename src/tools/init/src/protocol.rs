@@ -0,0 +1,216 @@
+/*
+ * Copyright (c) 2026 Antmicro <www.antmicro.com>
+ *
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Requests accepted by init and its replies, shared with the clients.
+//!
+//! Every request is a JSON [`Operation`], followed by a newline. The kernel
+//! fifo is answered with a line with the number of the request, followed by
+//! the pid of a spawned process or the JSON description of services. On the
+//! userspace fifo the operation can be wrapped in a [`Request`] naming a
+//! fifo created by the client, to which init writes a [`Reply`] as a line of
+//! JSON, e.g.
+//! `{"id":1,"ok":{"pid":5}}` or
+//! `{"id":1,"error":{"code":"not-found","message":"Service db not found"}}`.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use applet::errno;
+use applet::kernel::{ExitCode, Pid};
+
+/// Fifo through which userspace processes send requests to init.
+pub const FIFO_PATH: &str = "/dev/init.fifo";
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct SpawnArgs {
+    pub cmd: String,
+    pub stdin: Option<String>,
+    pub stdout: Option<String>,
+    pub stderr: Option<String>,
+    pub args: Vec<String>,
+    pub kern: bool,
+    /// Variables added to the environment of the process
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub env: HashMap<String, String>,
+    /// File with variables of the process, overridden by `env`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env_file: Option<String>,
+    /// Working directory of the process
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub enum Operation {
+    Start(String),
+    Stop(String),
+    Restart(String),
+    Spawn(SpawnArgs),
+    Status(String),
+    List,
+    /// Read /etc/init.d again and apply the changes
+    Reload,
+    /// Stop every service and unmount the filesystems, then reset the
+    /// system with `reboot`, or leave it halted
+    Shutdown {
+        reboot: bool,
+    },
+}
+
+/// Operation sent through the userspace fifo with the fifo to reply to.
+/// Plain operations are accepted there too, and are not answered.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Request {
+    pub operation: Operation,
+    /// Fifo to write the reply to, see [`is_reply_fifo`]
+    #[serde(default)]
+    pub reply: Option<String>,
+    /// Identifies the request in the reply, for clients sharing a fifo
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
+}
+
+/// Whether init replies to `path`. Only fifos in /dev named *.fifo are
+/// accepted, except the ones of init itself named init.*.fifo, so that a
+/// request can't make init write to other files, or into the fifos of the
+/// kernel and of the service logs.
+pub fn is_reply_fifo(path: &str) -> bool {
+    let path = Path::new(path);
+    path.parent() == Some(Path::new("/dev"))
+        && path
+            .extension()
+            .is_some_and(|extension| extension == "fifo")
+        && !path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with("init."))
+}
+
+/// State of a service as reported by the status requests.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Status {
+    Stopped,
+    /// Waiting for the services it depends on to start
+    Waiting,
+    Running,
+    Failed,
+    Restarting,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(match self {
+            Self::Stopped => "stopped",
+            Self::Waiting => "waiting",
+            Self::Running => "running",
+            Self::Failed => "failed",
+            Self::Restarting => "restarting",
+        })
+    }
+}
+
+/// Description of a service returned by the status and list requests.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ServiceStatus {
+    pub name: String,
+    pub pid: Option<Pid>,
+    pub state: Status,
+    /// Time since the process was spawned, while it runs
+    pub uptime_ms: Option<u64>,
+    pub restarts: usize,
+    pub last_exit: Option<ExitCode>,
+    /// The command followed by its arguments
+    pub command: Vec<String>,
+    pub stdout: String,
+    pub stderr: String,
+    /// Time the service has to exit once asked to stop, before it is killed
+    pub stop_timeout_ms: u64,
+}
+
+/// Result of a successful request.
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Response {
+    Done,
+    Pid(Pid),
+    Status(ServiceStatus),
+    List(Vec<ServiceStatus>),
+    Reloaded(ReloadSummary),
+}
+
+/// Services affected by a reload, by name.
+#[derive(Deserialize, Serialize, Debug, Default)]
+pub struct ReloadSummary {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<String>,
+}
+
+/// Why a request failed.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ErrorCode {
+    /// The request could not be parsed
+    InvalidRequest,
+    NotFound,
+    AlreadyRunning,
+    NotRunning,
+    /// Only status requests are served during a shutdown
+    ShuttingDown,
+    /// An operation of the kernel failed
+    Failed,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct RequestError {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+impl RequestError {
+    pub fn new<M: Into<String>>(code: ErrorCode, message: M) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn not_found(name: &str) -> Self {
+        Self::new(ErrorCode::NotFound, format!("Service {} not found", name))
+    }
+}
+
+impl From<io::Error> for RequestError {
+    fn from(e: io::Error) -> Self {
+        Self::new(ErrorCode::Failed, errno::describe(&e))
+    }
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Ok(Response),
+    Error(RequestError),
+}
+
+/// Reply to a request from the userspace fifo.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Reply {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
+    #[serde(flatten)]
+    pub outcome: Outcome,
+}
@@ -1,92 +1,1040 @@
-use std::fs;
+use std::fmt;
 use std::io;
+use std::io::Read;
 
-use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::mem;
+use std::time::{Duration, SystemTime};
 
+use serde::de::{self, Deserializer, IgnoredAny};
 use serde::Deserialize;
+use serde_json::Value;
 
-use wasi_ext_lib::{spawn, Redirect};
+use applet::errno::{self, WithPath};
+use applet::kernel::{ExitCode, Kernel, OpenFlags, Pid, Redirect, Signal};
+
+use crate::environment;
+use crate::logs::{self, ServiceLog};
+use crate::protocol::{ReloadSummary, RequestError, ServiceStatus, Status};
+
+const SERVICES_PATH: &str = "/etc/init.d";
+/// Time the processes killed during a shutdown have to exit, on top of the
+/// stop timeouts of the services
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+
+/// When a service is restarted after its process exits.
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum RestartPolicy {
+    #[default]
+    Never,
+    OnFailure,
+    Always,
+}
+
+/// How init treats the process of a service.
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum ServiceType {
+    /// Runs until it is stopped, its dependents start once it is spawned
+    #[default]
+    Simple,
+    /// Runs to completion, its dependents start once it exited
+    Oneshot,
+}
+
+/// Stage of the boot a service belongs to. A service is started after
+/// the services of the earlier targets.
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Target {
+    Sysinit,
+    Basic,
+    #[default]
+    Default,
+}
+
+impl Target {
+    pub const ALL: [Self; 3] = [Self::Sysinit, Self::Basic, Self::Default];
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(match self {
+            Self::Sysinit => "sysinit",
+            Self::Basic => "basic",
+            Self::Default => "default",
+        })
+    }
+}
+
+fn default_restart_delay_ms() -> u64 {
+    1000
+}
+
+fn default_restart_delay_max_ms() -> u64 {
+    30000
+}
+
+fn default_max_restarts() -> usize {
+    5
+}
+
+fn default_restart_window_ms() -> u64 {
+    60000
+}
+
+fn default_log_max_bytes() -> u64 {
+    65536
+}
+
+fn default_log_keep() -> usize {
+    3
+}
+
+fn default_stop_signal() -> Signal {
+    Signal::Term
+}
+
+fn default_stop_timeout_ms() -> u64 {
+    3000
+}
+
+fn deserialize_signal<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Signal, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    Signal::from_name(&name).ok_or_else(|| de::Error::custom(format!("unknown signal {}", name)))
+}
 
 #[derive(Deserialize, Debug)]
 pub(crate) struct Service {
     pub(crate) name: String,
     pub(crate) stdin: String,
-    pub(crate) stdout: String,
-    pub(crate) stderr: String,
+    /// File the output is appended to, captured in the log of the service
+    /// if there is none
+    #[serde(default)]
+    pub(crate) stdout: Option<String>,
+    #[serde(default)]
+    pub(crate) stderr: Option<String>,
     pub(crate) cmd: String,
     pub(crate) args: Vec<String>,
+    #[serde(rename = "type", default)]
+    pub(crate) kind: ServiceType,
+    #[serde(default)]
+    pub(crate) target: Target,
+    /// Variables added to the environment of the service
+    #[serde(default)]
+    pub(crate) env: HashMap<String, String>,
+    /// File with variables of the service, overridden by `env`
+    #[serde(default)]
+    pub(crate) env_file: Option<String>,
+    /// Working directory of the service
+    #[serde(default)]
+    pub(crate) cwd: Option<String>,
+    /// Services started before this one, which isn't started without them
+    #[serde(default)]
+    pub(crate) requires: Vec<String>,
+    /// Services started before this one, if they exist
+    #[serde(default)]
+    pub(crate) after: Vec<String>,
+    #[serde(default)]
+    pub(crate) restart: RestartPolicy,
+    /// Delay of the first restart, doubled for every restart in the window
+    #[serde(default = "default_restart_delay_ms")]
+    pub(crate) restart_delay_ms: u64,
+    #[serde(default = "default_restart_delay_max_ms")]
+    pub(crate) restart_delay_max_ms: u64,
+    /// Restarts allowed within the window before init gives up
+    #[serde(default = "default_max_restarts")]
+    pub(crate) max_restarts: usize,
+    #[serde(default = "default_restart_window_ms")]
+    pub(crate) restart_window_ms: u64,
+    /// Signal asking the service to terminate, unless it has a stop command
+    #[serde(
+        default = "default_stop_signal",
+        deserialize_with = "deserialize_signal"
+    )]
+    pub(crate) stop_signal: Signal,
+    /// Time after which a service that didn't stop is killed
+    #[serde(default = "default_stop_timeout_ms")]
+    pub(crate) stop_timeout_ms: u64,
+    /// Command with its arguments that makes the service terminate
+    #[serde(default)]
+    pub(crate) stop_cmd: Vec<String>,
+    /// Size of the log after which it is rotated
+    #[serde(default = "default_log_max_bytes")]
+    pub(crate) log_max_bytes: u64,
+    /// Rotated logs kept
+    #[serde(default = "default_log_keep")]
+    pub(crate) log_keep: usize,
+
+    /// JSON the service was read from, compared on reload
+    #[serde(skip)]
+    pub(crate) definition: Value,
+    #[serde(skip)]
+    pub(crate) state: ServiceState,
+}
+
+/// Service with the fields of its file that init doesn't know.
+#[derive(Deserialize)]
+struct ServiceFields {
+    /// Takes the fields init knows
+    #[serde(flatten)]
+    _service: Service,
+    #[serde(flatten)]
+    unknown: BTreeMap<String, IgnoredAny>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Severity {
+    Error,
+    Warning,
+}
 
-    #[serde(skip_deserializing)]
-    pub(crate) pid: i32,
+/// Problem found in a service file.
+#[derive(Clone, Debug)]
+pub(crate) struct Diagnostic {
+    pub(crate) path: String,
+    /// Line and column of the problem, if it has one
+    pub(crate) position: Option<(usize, usize)>,
+    pub(crate) severity: Severity,
+    pub(crate) message: String,
+}
+
+impl Diagnostic {
+    pub fn warning(path: &str, message: String) -> Self {
+        Self {
+            path: path.to_string(),
+            position: None,
+            severity: Severity::Warning,
+            message,
+        }
+    }
+
+    fn error(path: &str, e: &io::Error) -> Self {
+        Self {
+            path: path.to_string(),
+            position: None,
+            severity: Severity::Error,
+            message: errno::describe(e),
+        }
+    }
+
+    fn invalid(path: &str, e: &serde_json::Error) -> Self {
+        // The position is reported separately
+        let message = e.to_string();
+        let position = format!(" at line {} column {}", e.line(), e.column());
+        Self {
+            path: path.to_string(),
+            position: Some((e.line(), e.column())),
+            severity: Severity::Error,
+            message: message
+                .strip_suffix(&position)
+                .unwrap_or(&message)
+                .to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.path)?;
+        if let Some((line, column)) = self.position {
+            write!(f, ":{}:{}", line, column)?;
+        }
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, ": {}: {}", severity, self.message)
+    }
+}
+
+/// File read from /etc/init.d.
+pub(crate) struct ServiceFile {
+    pub(crate) path: String,
+    /// The service, unless the file is invalid
+    pub(crate) service: Option<Service>,
+    pub(crate) diagnostics: Vec<Diagnostic>,
+}
+
+fn read_service_file(kernel: &mut dyn Kernel, path: String) -> ServiceFile {
+    let mut content = String::new();
+    let result = kernel
+        .open(&path, OpenFlags::READ)
+        .and_then(|mut file| file.read_to_string(&mut content));
+    if let Err(e) = result {
+        return ServiceFile {
+            diagnostics: vec![Diagnostic::error(&path, &e)],
+            service: None,
+            path,
+        };
+    }
+
+    // Parsed on its own first, as collecting the unknown fields hides the
+    // position of errors
+    let (service, diagnostics) = match serde_json::from_str::<Service>(&content) {
+        Ok(mut service) => {
+            service.definition = serde_json::from_str(&content).unwrap_or_default();
+            let unknown = ServiceFields::deserialize(&service.definition)
+                .map(|fields| fields.unknown)
+                .unwrap_or_default();
+            let diagnostics = unknown
+                .into_keys()
+                .map(|field| Diagnostic::warning(&path, format!("unknown field `{}`", field)))
+                .collect();
+            (Some(service), diagnostics)
+        }
+        Err(e) => (None, vec![Diagnostic::invalid(&path, &e)]),
+    };
+    ServiceFile {
+        path,
+        service,
+        diagnostics,
+    }
+}
+
+/// Reads the files in /etc/init.d, ordered by path.
+pub(crate) fn read_service_files(kernel: &mut dyn Kernel) -> io::Result<Vec<ServiceFile>> {
+    let mut paths = kernel
+        .read_dir(SERVICES_PATH)
+        .with_path(SERVICES_PATH)?
+        .into_iter()
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect::<Vec<String>>();
+    paths.sort();
+    Ok(paths
+        .into_iter()
+        .map(|path| read_service_file(kernel, path))
+        .collect())
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Exit {
+    /// Exit status, unknown when the kernel doesn't report it
+    pub(crate) status: Option<ExitCode>,
+    pub(crate) time: SystemTime,
+    /// The process was stopped on request
+    pub(crate) stopped: bool,
+}
+
+/// What init knows about the process of a service.
+#[derive(Debug, Default)]
+pub(crate) struct ServiceState {
+    /// Pid of the running process
+    pub(crate) pid: Option<Pid>,
+    pub(crate) last_exit: Option<Exit>,
+    /// Clock reading at which the running process was spawned
+    pub(crate) started_at: Option<Duration>,
+    /// Set by a stop request, so that the exit doesn't cause a restart
+    pub(crate) stopping: bool,
+    /// Set by a restart request, to spawn the service again once it exits
+    pub(crate) restart_requested: bool,
+    /// Clock reading at which a process that didn't stop is killed
+    pub(crate) kill_at: Option<Duration>,
+    /// The last process failed to spawn or exited with an error
+    pub(crate) failed: bool,
+    /// Clock reading at which the service is due to be restarted
+    pub(crate) restart_at: Option<Duration>,
+    /// Clock readings of the restarts within the restart window
+    pub(crate) restarts: VecDeque<Duration>,
+    /// Restarts since init started
+    pub(crate) restart_count: usize,
+    /// The service file was removed, the service is forgotten once its
+    /// process exits
+    pub(crate) removed: bool,
+    /// The service is spawned once the services it depends on are started
+    pub(crate) waiting: bool,
+    /// The service was not started because a required service failed
+    pub(crate) skipped: bool,
+}
+
+/// Outcome of the exit of a service process.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Restart {
+    No,
+    After(Duration),
+    /// The service was restarted too many times within the window
+    GiveUp,
 }
 
 impl Service {
-    pub fn spawn(&mut self) -> io::Result<()> {
-        let (_, pid) = spawn(
-            &self.cmd,
-            &self
-                .args
-                .iter()
-                .map(|arg| arg.as_str())
-                .collect::<Vec<&str>>(),
-            &HashMap::new(),
-            true,
-            &[
-                Redirect::Read(0, self.stdin.clone()),
-                Redirect::Append(1, self.stdout.clone()),
-                Redirect::Append(2, self.stderr.clone()),
-            ],
-        )
-        .map_err(io::Error::from_raw_os_error)?;
-
-        self.pid = pid;
+    fn dependencies(&self) -> impl Iterator<Item = &String> {
+        self.requires.iter().chain(&self.after)
+    }
+
+    /// Whether services depending on this one have to wait for it: it is
+    /// waiting to be spawned, or it is a oneshot service still running.
+    fn is_starting(&self) -> bool {
+        self.state.waiting || (self.kind == ServiceType::Oneshot && self.state.pid.is_some())
+    }
+
+    /// Whether init captures some of the output of the service.
+    pub fn captures_output(&self) -> bool {
+        self.stdout.is_none() || self.stderr.is_none()
+    }
+
+    /// Path of an output stream, or the path made for the name of the
+    /// service if the stream is captured.
+    fn output(&self, path: &Option<String>, captured: fn(&str) -> String) -> String {
+        path.clone().unwrap_or_else(|| captured(&self.name))
+    }
+
+    /// Spawns a command with the standard streams, environment and working
+    /// directory of the service.
+    fn spawn_command(
+        &self,
+        kernel: &mut dyn Kernel,
+        cmd: &str,
+        args: &[String],
+    ) -> io::Result<Pid> {
+        let env = environment::child_env(
+            kernel,
+            self.env_file.as_deref(),
+            &self.env,
+            self.cwd.as_deref(),
+        )?;
+        let (_, pid) = kernel
+            .spawn(
+                cmd,
+                &args.iter().map(|arg| arg.as_str()).collect::<Vec<&str>>(),
+                &env,
+                true,
+                &[
+                    Redirect::Read(0, self.stdin.clone()),
+                    Redirect::Append(1, self.output(&self.stdout, logs::fifo_path)),
+                    Redirect::Append(2, self.output(&self.stderr, logs::fifo_path)),
+                ],
+            )
+            .with_path(cmd)?;
+        Ok(pid)
+    }
+
+    pub fn spawn(&mut self, kernel: &mut dyn Kernel) -> io::Result<()> {
+        let now = kernel.clock()?;
+        let result = self.spawn_command(kernel, &self.cmd, &self.args);
+        self.state.failed = result.is_err();
+        let pid = result?;
+
+        self.state.pid = Some(pid);
+        self.state.started_at = Some(now);
+        self.state.restart_at = None;
+        self.state.waiting = false;
+        self.state.skipped = false;
         Ok(())
     }
+
+    /// Asks the process of the service to terminate, with the stop command
+    /// or signal, and schedules killing it once the stop timeout passes.
+    pub fn stop(&mut self, kernel: &mut dyn Kernel, now: Duration) -> io::Result<()> {
+        self.state.restart_at = None;
+        self.state.waiting = false;
+        let Some(pid) = self.state.pid else {
+            return Ok(());
+        };
+        if self.state.kill_at.is_some() {
+            // Already stopping
+            return Ok(());
+        }
+        self.state.stopping = true;
+        self.state.kill_at = Some(now + Duration::from_millis(self.stop_timeout_ms));
+        match self.stop_cmd.split_first() {
+            Some((cmd, args)) => self.spawn_command(kernel, cmd, args).map(|_| ()),
+            None => kernel.kill(pid, self.stop_signal),
+        }
+    }
+
+    pub fn status(&self, now: Duration) -> ServiceStatus {
+        let state = if self.state.pid.is_some() {
+            Status::Running
+        } else if self.state.waiting {
+            Status::Waiting
+        } else if self.state.restart_at.is_some() {
+            Status::Restarting
+        } else if self.state.failed {
+            Status::Failed
+        } else {
+            Status::Stopped
+        };
+        ServiceStatus {
+            name: self.name.clone(),
+            pid: self.state.pid,
+            state,
+            uptime_ms: self
+                .state
+                .started_at
+                .map(|started_at| now.saturating_sub(started_at).as_millis() as u64),
+            restarts: self.state.restart_count,
+            last_exit: self.state.last_exit.and_then(|exit| exit.status),
+            command: [&self.cmd].into_iter().chain(&self.args).cloned().collect(),
+            stdout: self.output(&self.stdout, logs::log_path),
+            stderr: self.output(&self.stderr, logs::log_path),
+            stop_timeout_ms: self.stop_timeout_ms,
+        }
+    }
+
+    // The wasi kernel doesn't report the status of background children. An
+    // unknown status counts as a success for oneshot services, which are
+    // meant to exit, and as a failure for simple ones, which aren't.
+    fn succeeded(&self, status: Option<ExitCode>) -> bool {
+        status.map_or(self.kind == ServiceType::Oneshot, |status| status == 0)
+    }
+
+    /// Schedules a restart of the service after its process exited, or
+    /// failed to spawn, according to the restart policy.
+    fn schedule_restart(&mut self, failed: bool, now: Duration) -> Restart {
+        let restart = match self.restart {
+            RestartPolicy::Never => false,
+            RestartPolicy::OnFailure => failed,
+            RestartPolicy::Always => true,
+        };
+        let stopping = mem::take(&mut self.state.stopping);
+        if !restart || stopping {
+            return Restart::No;
+        }
+
+        let window = Duration::from_millis(self.restart_window_ms);
+        self.state
+            .restarts
+            .retain(|time| now.saturating_sub(*time) < window);
+        let count = self.state.restarts.len();
+        if count >= self.max_restarts {
+            return Restart::GiveUp;
+        }
+
+        let delay = Duration::from_millis(
+            self.restart_delay_ms
+                .saturating_mul(2u64.saturating_pow(count as u32))
+                .min(self.restart_delay_max_ms),
+        );
+        self.state.restart_at = Some(now + delay);
+        Restart::After(delay)
+    }
 }
 
 pub(crate) struct ServiceManager {
     pub(crate) services: HashMap<String, Service>,
+    /// Logs of the services capturing their output, by service name
+    pub(crate) logs: HashMap<String, ServiceLog>,
+    /// Last boot target reached
+    reached: Option<Target>,
+    /// Set by a shutdown, after which no service is started or restarted
+    /// anymore, to the time the services still running are given up on
+    shutdown_deadline: Option<Duration>,
 }
 
 impl ServiceManager {
     pub fn new() -> Self {
         Self {
             services: HashMap::new(),
+            logs: HashMap::new(),
+            reached: None,
+            shutdown_deadline: None,
         }
     }
 
-    fn read_services() -> io::Result<impl std::iter::Iterator<Item = io::Result<Service>>> {
-        Ok(
-            fs::read_dir("/etc/init.d")?.map(|entry| -> io::Result<Service> {
-                if let Ok(mut service) =
-                    serde_json::from_reader::<fs::File, Service>(fs::File::open(entry?.path())?)
-                {
-                    service.pid = -1;
-                    Ok(service)
-                } else {
-                    Err(io::Error::new(
-                        io::ErrorKind::InvalidInput,
-                        "Invalid init service",
-                    ))
+    /// Loads the services from /etc/init.d, returning the problems found in
+    /// their files. Invalid files are skipped.
+    pub fn load_services(&mut self, kernel: &mut dyn Kernel) -> io::Result<Vec<Diagnostic>> {
+        let mut diagnostics = Vec::new();
+        self.services = HashMap::new();
+        for file in read_service_files(kernel)? {
+            diagnostics.extend(file.diagnostics);
+            if let Some(service) = file.service {
+                self.services.insert(service.name.clone(), service);
+            }
+        }
+        Ok(diagnostics)
+    }
+
+    /// Records the exit of a service process and schedules its restart,
+    /// returning the service or `None` for processes that aren't services.
+    pub fn handle_exit(
+        &mut self,
+        pid: Pid,
+        status: Option<ExitCode>,
+        now: Duration,
+    ) -> Option<(&Service, Restart)> {
+        let service = self
+            .services
+            .values_mut()
+            .find(|service| service.state.pid == Some(pid))?;
+        service.state.pid = None;
+        service.state.started_at = None;
+        service.state.kill_at = None;
+        let stopped = service.state.stopping;
+        let succeeded = service.succeeded(status);
+        service.state.failed = !succeeded && !stopped;
+        service.state.last_exit = Some(Exit {
+            status,
+            time: SystemTime::now(),
+            stopped,
+        });
+        let mut restart = service.schedule_restart(!succeeded, now);
+        if mem::take(&mut service.state.restart_requested) {
+            service.state.restart_at = Some(now);
+            restart = Restart::After(Duration::ZERO);
+        }
+        if self.shutdown_deadline.is_some() {
+            service.state.restart_at = None;
+            restart = Restart::No;
+        }
+        Some((service, restart))
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Result<&mut Service, RequestError> {
+        self.services
+            .get_mut(name)
+            .filter(|service| !service.state.removed)
+            .ok_or_else(|| RequestError::not_found(name))
+    }
+
+    /// Forgets the removed services whose process exited.
+    pub fn forget_removed(&mut self) {
+        self.services
+            .retain(|_, service| !service.state.removed || service.state.pid.is_some());
+        let services = &self.services;
+        self.logs.retain(|name, _| services.contains_key(name));
+    }
+
+    /// Opens the logs of the services capturing their output, which must be
+    /// done before they are spawned, and applies the rotation settings of
+    /// the current definitions. Returns the problems to report.
+    pub fn open_logs(&mut self, kernel: &mut dyn Kernel) -> Vec<String> {
+        let mut reports = Vec::new();
+        let services = self
+            .services
+            .values()
+            .filter(|service| service.captures_output());
+        for service in services {
+            match self.logs.entry(service.name.clone()) {
+                Entry::Occupied(mut entry) => {
+                    let log = entry.get_mut();
+                    log.max_size = service.log_max_bytes;
+                    log.keep = service.log_keep;
+                }
+                Entry::Vacant(entry) => {
+                    match ServiceLog::open(
+                        kernel,
+                        &service.name,
+                        service.log_max_bytes,
+                        service.log_keep,
+                    ) {
+                        Ok(log) => {
+                            entry.insert(log);
+                        }
+                        Err(e) => reports.push(format!(
+                            "Could not open the log of {}: {}",
+                            service.name,
+                            errno::describe(&e)
+                        )),
+                    }
                 }
-            }),
-        )
+            }
+        }
+        reports
+    }
+
+    /// Reads /etc/init.d again and applies the changes: removed services
+    /// are stopped, running services whose definition changed are stopped
+    /// and spawned again, and new services are started in dependency order.
+    /// Other services are left alone. Returns what changed, with the
+    /// problems to report.
+    pub fn reload(
+        &mut self,
+        kernel: &mut dyn Kernel,
+        now: Duration,
+    ) -> io::Result<(ReloadSummary, Vec<String>)> {
+        let mut reports = Vec::new();
+        let mut loaded = HashMap::new();
+        for file in read_service_files(kernel)? {
+            reports.extend(file.diagnostics.iter().map(Diagnostic::to_string));
+            if let Some(service) = file.service {
+                loaded.insert(service.name.clone(), service);
+            }
+        }
+
+        let mut summary = ReloadSummary::default();
+        for (name, service) in &mut self.services {
+            if loaded.contains_key(name) || service.state.removed {
+                continue;
+            }
+            service.state.removed = true;
+            service.state.restart_requested = false;
+            if let Err(e) = service.stop(kernel, now) {
+                reports.push(format!("{}: {}", name, errno::describe(&e)));
+            }
+            summary.removed.push(name.clone());
+        }
+
+        let mut added = BTreeSet::new();
+        for (name, mut service) in loaded {
+            let Some(old) = self.services.get_mut(&name) else {
+                added.insert(name.clone());
+                self.services.insert(name, service);
+                continue;
+            };
+            if old.definition == service.definition && !old.state.removed {
+                continue;
+            }
+            // Unless a stop is in progress, the process is stopped with the
+            // old definition and spawned with the new one
+            if old.state.pid.is_some() && old.state.kill_at.is_none() {
+                old.state.restart_requested = true;
+                if let Err(e) = old.stop(kernel, now) {
+                    reports.push(format!("{}: {}", name, errno::describe(&e)));
+                }
+            }
+            if mem::take(&mut old.state.removed) {
+                // Added back while its process was stopping
+                old.state.restart_requested = true;
+                summary.removed.retain(|removed| *removed != name);
+                summary.added.push(name.clone());
+            } else {
+                summary.changed.push(name.clone());
+            }
+            service.state = mem::take(&mut old.state);
+            *old = service;
+        }
+        self.forget_removed();
+
+        reports.extend(self.open_logs(kernel));
+        reports.extend(self.start_services(kernel, &added));
+        summary.added.extend(added);
+        for names in [
+            &mut summary.added,
+            &mut summary.removed,
+            &mut summary.changed,
+        ] {
+            names.sort();
+        }
+        Ok((summary, reports))
+    }
+
+    /// Status of every service, ordered by name.
+    pub fn list(&self, now: Duration) -> Vec<ServiceStatus> {
+        let mut statuses = self
+            .services
+            .values()
+            .filter(|service| !service.state.removed)
+            .map(|service| service.status(now))
+            .collect::<Vec<ServiceStatus>>();
+        statuses.sort_by(|a, b| a.name.cmp(&b.name));
+        statuses
     }
 
-    pub fn load_services(&mut self) -> io::Result<()> {
-        self.services = Self::read_services()?
+    /// Pids of the running service processes.
+    pub fn pids(&self) -> Vec<Pid> {
+        self.services
+            .values()
+            .filter_map(|service| service.state.pid)
+            .collect()
+    }
+
+    /// Clock reading of the earliest scheduled restart or kill, or of the
+    /// end of the shutdown.
+    pub fn next_deadline(&self) -> Option<Duration> {
+        self.services
+            .values()
+            .flat_map(|service| [service.state.restart_at, service.state.kill_at])
+            .chain([self.shutdown_deadline])
             .flatten()
-            .map(|service| (service.name.clone(), service))
-            .collect::<HashMap<String, Service>>();
-        Ok(())
+            .min()
     }
 
-    pub fn spawn_services(&mut self) -> io::Result<()> {
+    /// Kills the services that didn't stop in time, returning their names
+    /// with the result of the kill.
+    pub fn kill_overdue(
+        &mut self,
+        kernel: &mut dyn Kernel,
+        now: Duration,
+    ) -> Vec<(String, io::Result<()>)> {
         self.services
             .values_mut()
-            .map(|service| service.spawn())
-            .filter(|r| r.is_err())
-            .for_each(|err| eprintln!("{:?}", err));
-        Ok(())
+            .filter(|service| service.state.kill_at.is_some_and(|at| at <= now))
+            .filter_map(|service| {
+                service.state.kill_at = None;
+                let pid = service.state.pid?;
+                Some((service.name.clone(), kernel.kill(pid, Signal::Kill)))
+            })
+            .collect()
+    }
+
+    /// Spawns the services due to be restarted, returning their names with
+    /// the result of the restart.
+    pub fn restart_due(
+        &mut self,
+        kernel: &mut dyn Kernel,
+        now: Duration,
+    ) -> Vec<(String, io::Result<()>, Restart)> {
+        self.services
+            .values_mut()
+            .filter(|service| service.state.restart_at.is_some_and(|at| at <= now))
+            .map(|service| {
+                service.state.restart_at = None;
+                service.state.restarts.push_back(now);
+                service.state.restart_count += 1;
+                let result = service.spawn(kernel);
+                // Nothing will exit if the spawn failed, so schedule the
+                // next attempt right away
+                let restart = if result.is_err() {
+                    service.schedule_restart(true, now)
+                } else {
+                    Restart::No
+                };
+                (service.name.clone(), result, restart)
+            })
+            .collect()
+    }
+
+    /// Services started before the service: its existing dependencies and
+    /// the services of the earlier targets.
+    fn dependencies_of<'a>(&'a self, service: &'a Service) -> BTreeSet<&'a String> {
+        let earlier = self
+            .services
+            .values()
+            .filter(|other| other.target < service.target)
+            .map(|other| &other.name);
+        service
+            .dependencies()
+            .filter(|name| self.services.contains_key(*name))
+            .chain(earlier)
+            .collect()
+    }
+
+    /// Orders the services so that each one comes after its dependencies,
+    /// by name where the order is free. Services in dependency cycles, or
+    /// depending on one, are returned separately.
+    fn start_order(&self) -> (Vec<String>, BTreeSet<String>) {
+        let mut pending = BTreeMap::new();
+        let mut dependents = HashMap::<&str, Vec<&str>>::new();
+        for service in self.services.values() {
+            let dependencies = self.dependencies_of(service);
+            for dependency in &dependencies {
+                dependents
+                    .entry(dependency.as_str())
+                    .or_default()
+                    .push(&service.name);
+            }
+            pending.insert(service.name.as_str(), dependencies.len());
+        }
+
+        let mut ready = pending
+            .iter()
+            .filter(|(_, count)| **count == 0)
+            .map(|(name, _)| *name)
+            .collect::<BTreeSet<&str>>();
+        let mut order = Vec::new();
+        while let Some(name) = ready.pop_first() {
+            pending.remove(name);
+            order.push(name.to_string());
+            for dependent in dependents.get(name).into_iter().flatten() {
+                let count = pending.get_mut(dependent).unwrap();
+                *count -= 1;
+                if *count == 0 {
+                    ready.insert(dependent);
+                }
+            }
+        }
+        let blocked = pending.into_keys().map(String::from).collect();
+        (order, blocked)
+    }
+
+    /// Finds a dependency cycle among the blocked services, as the list of
+    /// services along it ending with the first one.
+    fn find_cycle(&self, blocked: &BTreeSet<String>) -> Vec<String> {
+        // Every blocked service depends on another blocked one, so
+        // following the dependencies eventually comes back to a service
+        let mut path = vec![blocked.first().unwrap().clone()];
+        loop {
+            let next = self
+                .dependencies_of(&self.services[path.last().unwrap()])
+                .into_iter()
+                .find(|name| blocked.contains(*name))
+                .unwrap();
+            if let Some(start) = path.iter().position(|name| name == next) {
+                path.drain(..start);
+                path.push(next.clone());
+                return path;
+            }
+            path.push(next.clone());
+        }
+    }
+
+    /// Starts the services in dependency order, as in
+    /// [`Self::start_waiting`]. Returns the problems to report.
+    pub fn spawn_services(&mut self, kernel: &mut dyn Kernel) -> Vec<String> {
+        let names = self.services.keys().cloned().collect();
+        self.start_services(kernel, &names)
+    }
+
+    /// Starts some of the services, like [`Self::spawn_services`]. Services
+    /// in dependency cycles are not started.
+    fn start_services(&mut self, kernel: &mut dyn Kernel, names: &BTreeSet<String>) -> Vec<String> {
+        let mut reports = Vec::new();
+        let (_, blocked) = self.start_order();
+        if blocked.iter().any(|name| names.contains(name)) {
+            reports.push(format!(
+                "Dependency cycle: {}",
+                self.find_cycle(&blocked).join(" -> ")
+            ));
+            reports.extend(
+                blocked
+                    .iter()
+                    .filter(|name| names.contains(*name))
+                    .map(|name| format!("{}: not started because of a dependency cycle", name)),
+            );
+        }
+
+        for name in names.difference(&blocked) {
+            self.services.get_mut(name).unwrap().state.waiting = true;
+        }
+        reports.extend(self.start_waiting(kernel));
+        reports
+    }
+
+    /// Spawns the waiting services whose dependencies are spawned and whose
+    /// oneshot dependencies exited. Services whose required services are
+    /// missing or failed are skipped. Returns the problems to report.
+    pub fn start_waiting(&mut self, kernel: &mut dyn Kernel) -> Vec<String> {
+        let mut reports = Vec::new();
+        let (order, _) = self.start_order();
+        for name in order {
+            let service = &self.services[&name];
+            let ready = !self
+                .dependencies_of(service)
+                .into_iter()
+                .any(|dependency| self.services[dependency].is_starting());
+            if !service.state.waiting || !ready {
+                continue;
+            }
+            let missing = service
+                .requires
+                .iter()
+                .find(|required| {
+                    self.services
+                        .get(*required)
+                        .is_none_or(|required| required.state.failed || required.state.skipped)
+                })
+                .cloned();
+
+            if let Some(required) = &missing {
+                reports.push(if self.services.contains_key(required) {
+                    format!("{}: required service {} failed to start", name, required)
+                } else {
+                    format!("{}: required service {} not found", name, required)
+                });
+            }
+
+            let service = self.services.get_mut(&name).unwrap();
+            service.state.waiting = false;
+            if missing.is_some() {
+                service.state.skipped = true;
+            } else if let Err(e) = service.spawn(kernel) {
+                reports.push(format!("{}: {}", name, errno::describe(&e)));
+            }
+        }
+        reports
+    }
+
+    /// Cancels the pending starts and restarts, which are not done anymore,
+    /// before the services are stopped with [`Self::stop_for_shutdown`].
+    /// The services are given the time to stop one after the other, and to
+    /// exit once killed.
+    pub fn begin_shutdown(&mut self, now: Duration) {
+        let stop_timeout_ms = self
+            .services
+            .values()
+            .filter(|service| service.state.pid.is_some())
+            .map(|service| service.stop_timeout_ms)
+            .sum();
+        self.shutdown_deadline =
+            Some(now + Duration::from_millis(stop_timeout_ms) + SHUTDOWN_GRACE);
+        for service in self.services.values_mut() {
+            service.state.waiting = false;
+            service.state.restart_at = None;
+            service.state.restart_requested = false;
+        }
+    }
+
+    /// Stops the running services that no other running service depends
+    /// on, so that services are stopped in reverse dependency order, and
+    /// stops them again if they are still running after being killed.
+    /// Returns whether every service exited or the shutdown timed out, with
+    /// the problems to report.
+    pub fn stop_for_shutdown(
+        &mut self,
+        kernel: &mut dyn Kernel,
+        now: Duration,
+    ) -> (bool, Vec<String>) {
+        let mut running = self
+            .services
+            .values()
+            .filter(|service| service.state.pid.is_some())
+            .collect::<Vec<&Service>>();
+        if running.is_empty() {
+            return (true, Vec::new());
+        }
+        if self.shutdown_deadline.is_some_and(|at| at <= now) {
+            running.sort_by(|a, b| a.name.cmp(&b.name));
+            let names = running
+                .iter()
+                .map(|service| service.name.as_str())
+                .collect::<Vec<&str>>();
+            let report = format!(
+                "Services still running at the end of the shutdown: {}",
+                names.join(", ")
+            );
+            return (true, vec![report]);
+        }
+        let needed = running
+            .iter()
+            .flat_map(|service| self.dependencies_of(service))
+            .collect::<HashSet<&String>>();
+        // A service is stopping until it is killed, whether the kill worked
+        // or not
+        let mut names = running
+            .iter()
+            .filter(|service| service.state.kill_at.is_none() && !needed.contains(&service.name))
+            .map(|service| service.name.clone())
+            .collect::<Vec<String>>();
+        if names.is_empty()
+            && running
+                .iter()
+                .all(|service| service.state.kill_at.is_none())
+        {
+            // Services started in a dependency cycle are stopped together
+            names = running.iter().map(|service| service.name.clone()).collect();
+        }
+
+        let mut reports = Vec::new();
+        for name in names {
+            if let Err(e) = self.services.get_mut(&name).unwrap().stop(kernel, now) {
+                reports.push(format!("{}: {}", name, errno::describe(&e)));
+            }
+        }
+        (false, reports)
+    }
+
+    /// Boot targets reached since the last call, in order. A target is
+    /// reached once the services of it and of the earlier targets are
+    /// spawned, or skipped, and its oneshot services exited.
+    pub fn reach_targets(&mut self) -> Vec<Target> {
+        let mut reached = Vec::new();
+        for target in Target::ALL {
+            if self.reached >= Some(target) {
+                continue;
+            }
+            let starting = self
+                .services
+                .values()
+                .any(|service| service.target <= target && service.is_starting());
+            if starting {
+                break;
+            }
+            self.reached = Some(target);
+            reached.push(target);
+        }
+        reached
     }
 }
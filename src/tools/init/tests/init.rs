@@ -0,0 +1,1372 @@
+/*
+ * Copyright (c) 2026 Antmicro <www.antmicro.com>
+ *
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use std::time::Duration;
+
+use applet::kernel::{Kernel, Redirect, Signal, FIFO_DEV};
+use applet::Context;
+use mock_kernel::MockKernel;
+
+const KERNEL_FIFO_READ: &str = "/dev/initr.kfifo";
+const KERNEL_FIFO_WRITE: &str = "/dev/initw.kfifo";
+const FIFO: &str = "/dev/init.fifo";
+
+// Logged once every service is started
+const BOOTED: &str = "Reached target sysinit\n\
+                      Reached target basic\n\
+                      Reached target default\n\
+                      Boot completed in 0 ms\n";
+
+// init serves requests until the mock kernel runs out of events
+fn run(kernel: &MockKernel) {
+    let mut ctx = Context::new(&b""[..], Vec::new(), Vec::new()).with_kernel(kernel.clone());
+    let e = init::init(Vec::<String>::new(), &mut ctx).unwrap_err();
+    assert_eq!(e.message(), Some("No more events in the mock kernel"));
+}
+
+// `fields` are added to the JSON description of the service
+fn service_json(name: &str, cmd: &str, fields: &str) -> String {
+    format!(
+        r#"{{
+            "name": "{name}",
+            "stdin": "/dev/null",
+            "stdout": "/tmp/{name}.log",
+            "stderr": "/tmp/{name}.log",
+            "cmd": "{cmd}",
+            "args": ["--verbose"]{fields}
+        }}"#
+    )
+}
+
+fn add_service_with(kernel: &MockKernel, name: &str, cmd: &str, fields: &str) {
+    kernel.add_file(
+        &format!("/etc/init.d/{}", name),
+        service_json(name, cmd, fields),
+    );
+}
+
+fn add_service(kernel: &MockKernel, name: &str, cmd: &str) {
+    add_service_with(kernel, name, cmd, "");
+}
+
+fn log(kernel: &MockKernel) -> String {
+    String::from_utf8(kernel.file("/tmp/init.log").unwrap()).unwrap()
+}
+
+fn responses(kernel: &MockKernel) -> String {
+    String::from_utf8(kernel.file(KERNEL_FIFO_WRITE).unwrap()).unwrap()
+}
+
+#[test]
+fn creates_fifos_and_log() {
+    let kernel = MockKernel::new();
+
+    run(&kernel);
+
+    assert!(kernel.is_fifo("/dev/init.fifo"));
+    assert!(kernel.is_fifo(KERNEL_FIFO_READ));
+    assert!(kernel.is_fifo(KERNEL_FIFO_WRITE));
+    assert_eq!(log(&kernel), BOOTED);
+}
+
+#[test]
+fn keeps_log_of_previous_boot() {
+    let kernel = MockKernel::new();
+    kernel.add_file("/tmp/init.log", "System halted\n");
+
+    run(&kernel);
+
+    assert_eq!(log(&kernel), "System halted\n".to_string() + BOOTED);
+}
+
+#[test]
+fn spawns_services() {
+    let kernel = MockKernel::new();
+    add_service(&kernel, "httpd", "/usr/bin/httpd");
+
+    run(&kernel);
+
+    let processes = kernel.processes();
+    assert_eq!(processes.len(), 1);
+    assert_eq!(processes[0].path, "/usr/bin/httpd");
+    assert_eq!(processes[0].args, ["--verbose"]);
+    assert_eq!(
+        processes[0].redirects,
+        [
+            Redirect::Read(0, String::from("/dev/null")),
+            Redirect::Append(1, String::from("/tmp/httpd.log")),
+            Redirect::Append(2, String::from("/tmp/httpd.log")),
+        ]
+    );
+}
+
+#[test]
+fn skips_invalid_services() {
+    let kernel = MockKernel::new();
+    add_service(&kernel, "httpd", "/usr/bin/httpd");
+    kernel.add_file("/etc/init.d/broken", "{");
+
+    run(&kernel);
+
+    assert_eq!(kernel.processes().len(), 1);
+    assert_eq!(
+        log(&kernel),
+        "/etc/init.d/broken:1:1: error: EOF while parsing an object\n".to_string() + BOOTED
+    );
+}
+
+#[test]
+fn stops_and_starts_service() {
+    let kernel = MockKernel::new();
+    add_service(&kernel, "httpd", "/usr/bin/httpd");
+    kernel.feed(KERNEL_FIFO_READ, r#"{"Stop": "httpd"}"#);
+    kernel.feed(KERNEL_FIFO_READ, r#"{"Start": "httpd"}"#);
+
+    run(&kernel);
+
+    let processes = kernel.processes();
+    assert_eq!(processes.len(), 2);
+    assert_eq!(processes[0].signals, [Signal::Term]);
+    assert!(!processes[0].running);
+    assert!(processes[1].running);
+    assert_eq!(responses(&kernel), "0\n1\n");
+}
+
+#[test]
+fn starts_crashed_service_again() {
+    let kernel = MockKernel::new();
+    add_service(&kernel, "httpd", "/usr/bin/httpd");
+    kernel.crash_on_spawn("/usr/bin/httpd", 1);
+    kernel.feed(KERNEL_FIFO_READ, r#"{"Start": "httpd"}"#);
+
+    run(&kernel);
+
+    assert_eq!(kernel.processes().len(), 2);
+    assert_eq!(responses(&kernel), "0\n");
+    assert_eq!(
+        log(&kernel)
+            .matches("httpd exited with status 1 at ")
+            .count(),
+        2
+    );
+}
+
+#[test]
+fn restarts_failed_service_with_backoff() {
+    let kernel = MockKernel::new();
+    add_service_with(
+        &kernel,
+        "httpd",
+        "/usr/bin/httpd",
+        r#", "restart": "on-failure", "restart_delay_ms": 100,
+            "restart_delay_max_ms": 250, "max_restarts": 4"#,
+    );
+    kernel.crash_on_spawn("/usr/bin/httpd", 1);
+
+    run(&kernel);
+
+    assert_eq!(kernel.processes().len(), 5);
+    let log = log(&kernel);
+    let delays = log
+        .lines()
+        .filter_map(|line| line.strip_prefix("httpd will be restarted in "))
+        .collect::<Vec<&str>>();
+    assert_eq!(delays, ["100 ms", "200 ms", "250 ms", "250 ms"]);
+    assert_eq!(log.matches("Restarted httpd").count(), 4);
+    assert!(log.ends_with("httpd was restarted too many times, giving up\n"));
+}
+
+#[test]
+fn restarts_only_on_failure() {
+    let kernel = MockKernel::new();
+    add_service_with(
+        &kernel,
+        "httpd",
+        "/usr/bin/httpd",
+        r#", "restart": "on-failure""#,
+    );
+    kernel.crash_on_spawn("/usr/bin/httpd", 0);
+
+    run(&kernel);
+
+    assert_eq!(kernel.processes().len(), 1);
+}
+
+#[test]
+fn restarts_service_exiting_with_unknown_status_on_failure() {
+    let kernel = MockKernel::new();
+    add_service_with(
+        &kernel,
+        "httpd",
+        "/usr/bin/httpd",
+        r#", "restart": "on-failure", "max_restarts": 1"#,
+    );
+    kernel.hide_exit_statuses();
+    kernel.crash_on_spawn("/usr/bin/httpd", 0);
+
+    run(&kernel);
+
+    assert_eq!(kernel.processes().len(), 2);
+    let log = log(&kernel);
+    assert!(log.contains("httpd exited at "));
+    assert!(!log.contains("with status"));
+}
+
+#[test]
+fn restarts_service_crashing_after_being_stopped_and_started() {
+    let kernel = MockKernel::new();
+    add_service_with(
+        &kernel,
+        "httpd",
+        "/usr/bin/httpd",
+        r#", "restart": "on-failure", "max_restarts": 1"#,
+    );
+    add_service(&kernel, "db", "/usr/bin/db");
+    kernel.crash_on_spawn_after("/usr/bin/httpd", 1, 1);
+    kernel.crash_on_spawn_after("/usr/bin/db", 1, 1);
+    for name in ["httpd", "db"] {
+        kernel.feed(KERNEL_FIFO_READ, format!(r#"{{"Stop": "{}"}}"#, name));
+        kernel.feed(KERNEL_FIFO_READ, format!(r#"{{"Start": "{}"}}"#, name));
+    }
+    kernel.feed(KERNEL_FIFO_READ, r#"{"Status": "db"}"#);
+
+    run(&kernel);
+
+    assert_eq!(
+        spawned(&kernel),
+        [
+            "/usr/bin/db",
+            "/usr/bin/httpd",
+            "/usr/bin/httpd",
+            "/usr/bin/db",
+            "/usr/bin/httpd"
+        ]
+    );
+    let log = log(&kernel);
+    assert_eq!(log.matches("Restarted httpd").count(), 1);
+    assert_eq!(log.matches(" stopped\n").count(), 2);
+    assert!(responses(&kernel).contains(r#""name":"db","pid":null,"state":"failed""#));
+}
+
+#[test]
+fn always_restarts() {
+    let kernel = MockKernel::new();
+    add_service_with(
+        &kernel,
+        "httpd",
+        "/usr/bin/httpd",
+        r#", "restart": "always", "max_restarts": 2"#,
+    );
+    kernel.crash_on_spawn("/usr/bin/httpd", 0);
+
+    run(&kernel);
+
+    assert_eq!(kernel.processes().len(), 3);
+}
+
+#[test]
+fn does_not_restart_stopped_service() {
+    let kernel = MockKernel::new();
+    add_service_with(
+        &kernel,
+        "httpd",
+        "/usr/bin/httpd",
+        r#", "restart": "always""#,
+    );
+    kernel.feed(KERNEL_FIFO_READ, r#"{"Stop": "httpd"}"#);
+
+    run(&kernel);
+
+    let processes = kernel.processes();
+    assert_eq!(processes.len(), 1);
+    assert!(!processes[0].running);
+    assert!(!log(&kernel).contains("restarted"));
+}
+
+#[test]
+fn ignores_exits_of_other_processes() {
+    let kernel = MockKernel::new();
+    kernel.feed(
+        KERNEL_FIFO_READ,
+        r#"{"Spawn": {
+            "cmd": "/usr/bin/hello",
+            "stdin": "/dev/null",
+            "stdout": "/dev/null",
+            "stderr": "/dev/null",
+            "args": [],
+            "kern": false
+        }}"#,
+    );
+    kernel.crash_on_spawn("/usr/bin/hello", 0);
+
+    run(&kernel);
+
+    assert!(!kernel.processes()[0].running);
+    assert_eq!(log(&kernel), BOOTED);
+}
+
+#[test]
+fn spawns_process_with_redirects() {
+    let kernel = MockKernel::new();
+    kernel.feed(
+        KERNEL_FIFO_READ,
+        r#"{"Spawn": {
+            "cmd": "/usr/bin/hello",
+            "stdin": null,
+            "stdout": "/tmp/hello.out",
+            "stderr": null,
+            "args": ["world"],
+            "kern": true
+        }}"#,
+    );
+
+    run(&kernel);
+
+    let processes = kernel.processes();
+    assert_eq!(processes.len(), 1);
+    assert_eq!(processes[0].args, ["world"]);
+    assert_eq!(
+        processes[0].redirects,
+        [
+            Redirect::Read(0, String::from("/dev/spawn_stdin.0")),
+            Redirect::Append(1, String::from("/tmp/hello.out")),
+            Redirect::Append(2, String::from("/dev/spawn_stderr.0")),
+        ]
+    );
+    assert!(kernel.is_fifo("/dev/spawn_stdin.0"));
+    assert!(!kernel.is_fifo("/dev/spawn_stdout.0"));
+    assert_eq!(responses(&kernel), format!("0 {}\n", processes[0].pid));
+}
+
+#[test]
+fn logs_invalid_requests() {
+    let kernel = MockKernel::new();
+    kernel.feed(KERNEL_FIFO_READ, "not json");
+    kernel.feed(KERNEL_FIFO_READ, r#"{"Start": "missing"}"#);
+
+    run(&kernel);
+
+    assert!(!kernel.file("/tmp/init.log").unwrap().is_empty());
+    // Unknown services are not answered
+    assert_eq!(responses(&kernel), "");
+}
+
+fn spawned(kernel: &MockKernel) -> Vec<String> {
+    kernel
+        .processes()
+        .into_iter()
+        .map(|process| process.path)
+        .collect()
+}
+
+#[test]
+fn starts_services_in_dependency_order() {
+    let kernel = MockKernel::new();
+    add_service_with(&kernel, "a-web", "/usr/bin/web", r#", "requires": ["db"]"#);
+    add_service_with(&kernel, "db", "/usr/bin/db", r#", "after": ["mounts"]"#);
+    add_service(&kernel, "mounts", "/usr/bin/mounts");
+    add_service_with(
+        &kernel,
+        "z-clock",
+        "/usr/bin/clock",
+        r#", "after": ["ntp"]"#,
+    );
+
+    run(&kernel);
+
+    assert_eq!(
+        spawned(&kernel),
+        [
+            "/usr/bin/mounts",
+            "/usr/bin/db",
+            "/usr/bin/web",
+            "/usr/bin/clock"
+        ]
+    );
+    assert_eq!(log(&kernel), BOOTED);
+}
+
+#[test]
+fn reports_dependency_cycles() {
+    let kernel = MockKernel::new();
+    add_service_with(&kernel, "a", "/usr/bin/a", r#", "requires": ["b"]"#);
+    add_service_with(&kernel, "b", "/usr/bin/b", r#", "after": ["a"]"#);
+    add_service_with(&kernel, "c", "/usr/bin/c", r#", "after": ["b"]"#);
+    add_service(&kernel, "d", "/usr/bin/d");
+
+    run(&kernel);
+
+    assert_eq!(spawned(&kernel), ["/usr/bin/d"]);
+    assert_eq!(
+        log(&kernel),
+        "Dependency cycle: a -> b -> a\n\
+         a: not started because of a dependency cycle\n\
+         b: not started because of a dependency cycle\n\
+         c: not started because of a dependency cycle\n"
+            .to_string()
+            + BOOTED
+    );
+}
+
+#[test]
+fn skips_services_with_failed_requirements() {
+    let kernel = MockKernel::new();
+    kernel.fail_spawn("/usr/bin/db");
+    add_service(&kernel, "db", "/usr/bin/db");
+    add_service_with(&kernel, "web", "/usr/bin/web", r#", "requires": ["db"]"#);
+    add_service_with(
+        &kernel,
+        "proxy",
+        "/usr/bin/proxy",
+        r#", "requires": ["web"]"#,
+    );
+    add_service_with(&kernel, "cache", "/usr/bin/cache", r#", "after": ["db"]"#);
+    add_service_with(&kernel, "api", "/usr/bin/api", r#", "requires": ["auth"]"#);
+
+    run(&kernel);
+
+    assert_eq!(spawned(&kernel), ["/usr/bin/cache"]);
+    assert_eq!(
+        log(&kernel),
+        "api: required service auth not found\n\
+         db: /usr/bin/db: No such file or directory\n\
+         web: required service db failed to start\n\
+         proxy: required service web failed to start\n"
+            .to_string()
+            + BOOTED
+    );
+}
+
+#[test]
+fn reports_status_of_service() {
+    let kernel = MockKernel::new();
+    add_service(&kernel, "httpd", "/usr/bin/httpd");
+    kernel.feed(KERNEL_FIFO_READ, r#"{"Status": "httpd"}"#);
+
+    run(&kernel);
+
+    let pid = kernel.processes()[0].pid;
+    assert_eq!(
+        responses(&kernel),
+        format!(
+            "0 {{\"name\":\"httpd\",\"pid\":{},\"state\":\"running\",\"uptime_ms\":0,\
+             \"restarts\":0,\"last_exit\":null,\"command\":[\"/usr/bin/httpd\",\"--verbose\"],\
+             \"stdout\":\"/tmp/httpd.log\",\"stderr\":\"/tmp/httpd.log\",\"stop_timeout_ms\":3000}}\n",
+            pid
+        )
+    );
+}
+
+#[test]
+fn lists_services() {
+    let kernel = MockKernel::new();
+    kernel.crash_on_spawn("/usr/bin/crashing", 1);
+    kernel.crash_on_spawn("/usr/bin/flaky", 1);
+    add_service(&kernel, "crashing", "/usr/bin/crashing");
+    add_service_with(
+        &kernel,
+        "flaky",
+        "/usr/bin/flaky",
+        r#", "restart": "on-failure""#,
+    );
+    add_service(&kernel, "httpd", "/usr/bin/httpd");
+    add_service_with(
+        &kernel,
+        "stopped",
+        "/usr/bin/stopped",
+        r#", "requires": ["missing"]"#,
+    );
+    // The mock kernel reports one exit per poll, so the list is
+    // requested after both crashes are handled
+    kernel.feed(KERNEL_FIFO_READ, r#"{"Status": "httpd"}"#);
+    kernel.feed(KERNEL_FIFO_READ, r#"{"List": null}"#);
+    kernel.feed(KERNEL_FIFO_READ, r#"{"Status": "missing"}"#);
+
+    run(&kernel);
+
+    let response = responses(&kernel);
+    let list = response.lines().nth(1).unwrap().strip_prefix("1 ").unwrap();
+    let states = list
+        .split("\"state\":\"")
+        .skip(1)
+        .map(|rest| rest.split('"').next().unwrap())
+        .collect::<Vec<&str>>();
+    assert_eq!(states, ["failed", "restarting", "running", "stopped"]);
+    assert!(list.contains(r#""name":"crashing","pid":null"#));
+    assert!(list.contains(r#""uptime_ms":null,"restarts":0,"last_exit":1"#));
+    // Unknown services are not answered
+    assert_eq!(response.lines().count(), 2);
+}
+
+#[test]
+fn restarts_service_on_request() {
+    let kernel = MockKernel::new();
+    add_service(&kernel, "httpd", "/usr/bin/httpd");
+    add_service(&kernel, "stopped", "/usr/bin/stopped");
+    kernel.feed(KERNEL_FIFO_READ, r#"{"Stop": "stopped"}"#);
+    kernel.feed(KERNEL_FIFO_READ, r#"{"Restart": "httpd"}"#);
+    kernel.feed(KERNEL_FIFO_READ, r#"{"Restart": "stopped"}"#);
+
+    run(&kernel);
+
+    let processes = kernel.processes();
+    assert_eq!(
+        spawned(&kernel),
+        [
+            "/usr/bin/httpd",
+            "/usr/bin/stopped",
+            "/usr/bin/stopped",
+            "/usr/bin/httpd"
+        ]
+    );
+    assert_eq!(processes[0].signals, [Signal::Term]);
+    assert!(processes[3].running);
+    assert!(log(&kernel).ends_with("Restarted httpd\n"));
+    assert_eq!(responses(&kernel), "0\n1\n2\n");
+}
+
+#[test]
+fn replies_to_userspace_requests() {
+    let kernel = MockKernel::new();
+    kernel.clone().mknod("/dev/reply.fifo", FIFO_DEV).unwrap();
+    add_service(&kernel, "httpd", "/usr/bin/httpd");
+    kernel.feed(FIFO, r#"{"Stop": "httpd"}"#);
+    kernel.feed(
+        FIFO,
+        r#"{"operation": {"Status": "httpd"}, "reply": "/dev/reply.fifo"}"#,
+    );
+    kernel.feed(
+        FIFO,
+        r#"{"operation": {"Start": "httpd"}, "reply": "/dev/missing.fifo"}"#,
+    );
+
+    run(&kernel);
+
+    assert!(!kernel.processes()[0].running);
+    let reply = String::from_utf8(kernel.file("/dev/reply.fifo").unwrap()).unwrap();
+    assert!(reply.starts_with(r#"{"ok":{"status":{"name":"httpd","pid":null,"state":"stopped""#));
+    assert!(kernel.processes()[1].running);
+    assert!(
+        log(&kernel).ends_with("Could not reply: /dev/missing.fifo: No such file or directory\n")
+    );
+    assert_eq!(responses(&kernel), "");
+}
+
+#[test]
+fn rejects_requests_replying_outside_dev_fifos() {
+    let kernel = MockKernel::new();
+    kernel.add_file("/etc/passwd", "root:x:0:0::/root:/bin/sh\n");
+    kernel.add_file("/dev/tty", "");
+    add_service(&kernel, "httpd", "/usr/bin/httpd");
+    for path in ["/etc/passwd", "/dev/../etc/passwd.fifo", "/dev/tty"] {
+        kernel.feed(
+            FIFO,
+            format!(
+                r#"{{"operation": {{"Stop": "httpd"}}, "reply": "{}"}}"#,
+                path
+            ),
+        );
+    }
+    kernel.feed(FIFO, r#"{"operation": "Bogus", "reply": "/dev/tty"}"#);
+
+    run(&kernel);
+
+    assert!(kernel.processes()[0].running);
+    assert_eq!(
+        kernel.file("/etc/passwd").unwrap(),
+        b"root:x:0:0::/root:/bin/sh\n"
+    );
+    assert_eq!(kernel.file("/dev/tty").unwrap(), b"");
+    let log = log(&kernel);
+    assert!(log.contains("Request rejected: /etc/passwd: Not a fifo in /dev to reply to\n"));
+    assert!(log.contains("Request rejected: /dev/../etc/passwd.fifo: "));
+    assert!(log.contains("Request rejected: /dev/tty: "));
+}
+
+#[test]
+fn rejects_requests_replying_to_fifos_of_init() {
+    let kernel = MockKernel::new();
+    add_logged_service(&kernel, "httpd", "/usr/bin/httpd", "");
+    for path in [
+        KERNEL_FIFO_WRITE,
+        KERNEL_FIFO_READ,
+        FIFO,
+        "/dev/init.httpd.log.fifo",
+    ] {
+        kernel.feed(
+            FIFO,
+            format!(r#"{{"operation": "List", "reply": "{}"}}"#, path),
+        );
+    }
+
+    run(&kernel);
+
+    assert_eq!(responses(&kernel), "");
+    assert!(kernel
+        .file("/var/log/httpd.log")
+        .unwrap_or_default()
+        .is_empty());
+    let log = log(&kernel);
+    for path in [
+        KERNEL_FIFO_WRITE,
+        KERNEL_FIFO_READ,
+        FIFO,
+        "/dev/init.httpd.log.fifo",
+    ] {
+        assert!(log.contains(&format!(
+            "Request rejected: {}: Not a fifo in /dev to reply to\n",
+            path
+        )));
+    }
+}
+
+#[test]
+fn replies_with_errors() {
+    let kernel = MockKernel::new();
+    kernel.clone().mknod("/dev/reply.fifo", FIFO_DEV).unwrap();
+    add_service(&kernel, "httpd", "/usr/bin/httpd");
+    add_service_with(&kernel, "db", "/usr/bin/db", r#", "requires": ["missing"]"#);
+    for request in [
+        r#"{"operation": {"Start": "httpd"}, "reply": "/dev/reply.fifo", "id": 1}"#,
+        r#"{"operation": {"Stop": "db"}, "reply": "/dev/reply.fifo", "id": 2}"#,
+        r#"{"operation": {"Status": "web"}, "reply": "/dev/reply.fifo"}"#,
+        r#"{"operation": {"Reboot": true}, "reply": "/dev/reply.fifo", "id": 4}"#,
+        r#"{"operation": "List", "reply": "/dev/reply.fifo", "id": 5}"#,
+    ] {
+        kernel.feed(FIFO, request);
+    }
+
+    run(&kernel);
+
+    let reply = String::from_utf8(kernel.file("/dev/reply.fifo").unwrap()).unwrap();
+    let lines = reply.lines().collect::<Vec<&str>>();
+    assert_eq!(lines.len(), 5);
+    assert_eq!(
+        lines[0],
+        r#"{"id":1,"error":{"code":"already-running","message":"Service httpd is already running"}}"#
+    );
+    assert_eq!(
+        lines[1],
+        r#"{"id":2,"error":{"code":"not-running","message":"Service db is not running"}}"#
+    );
+    assert_eq!(
+        lines[2],
+        r#"{"error":{"code":"not-found","message":"Service web not found"}}"#
+    );
+    assert!(lines[3].starts_with(
+        r#"{"id":4,"error":{"code":"invalid-request","message":"unknown variant `Reboot`"#
+    ));
+    assert!(lines[4].starts_with(r#"{"id":5,"ok":{"list":[{"name":"db","#));
+    assert!(log(&kernel).contains("Request failed: Service web not found\n"));
+}
+
+#[test]
+fn reads_coalesced_and_partial_messages() {
+    let kernel = MockKernel::new();
+    for name in ["a", "b", "c"] {
+        add_service(&kernel, name, &format!("/usr/bin/{}", name));
+    }
+    kernel.feed(
+        KERNEL_FIFO_READ,
+        "{\"Stop\": \"a\"}\n{\"Stop\": \"b\"}\n{\"Sto",
+    );
+    kernel.feed(KERNEL_FIFO_READ, "p\": \"c\"}\n");
+
+    run(&kernel);
+
+    assert!(kernel.processes().iter().all(|process| !process.running));
+    assert_eq!(responses(&kernel), "0\n1\n2\n");
+    assert!(!log(&kernel).contains("Invalid"));
+}
+
+#[test]
+fn reads_requests_split_at_any_point() {
+    let kernel = MockKernel::new();
+    kernel.clone().mknod("/dev/reply.fifo", FIFO_DEV).unwrap();
+    add_service(&kernel, "a", "/usr/bin/a");
+    add_service(&kernel, "b", "/usr/bin/b");
+    kernel.set_fifo_read_size(3);
+    kernel.feed(
+        FIFO,
+        "{\"operation\": {\"Stop\": \"a\"}, \"reply\": \"/dev/reply.fifo\"}\n",
+    );
+    kernel.feed(
+        FIFO,
+        "{\"operation\": {\"Stop\": \"b\"}, \"reply\": \"/dev/reply.fifo\"}\n",
+    );
+    kernel.feed(
+        KERNEL_FIFO_READ,
+        "{\"Status\": \"a\"}\n{\"Status\": \"b\"}\n",
+    );
+
+    run(&kernel);
+
+    assert!(kernel.processes().iter().all(|process| !process.running));
+    let reply = String::from_utf8(kernel.file("/dev/reply.fifo").unwrap()).unwrap();
+    assert_eq!(reply, "{\"ok\":\"done\"}\n{\"ok\":\"done\"}\n");
+    assert_eq!(responses(&kernel).lines().count(), 2);
+    assert!(!log(&kernel).contains("Invalid"));
+}
+
+#[test]
+fn recovers_from_invalid_messages() {
+    let kernel = MockKernel::new();
+    add_service(&kernel, "a", "/usr/bin/a");
+    add_service(&kernel, "b", "/usr/bin/b");
+    kernel.feed(KERNEL_FIFO_READ, "garbage\n{\"Stop\": \"a\"}\n");
+    kernel.feed(KERNEL_FIFO_READ, format!("[{}", "1,".repeat(40000)));
+    kernel.feed(KERNEL_FIFO_READ, "1]\n{\"Stop\": \"b\"}\n");
+
+    run(&kernel);
+
+    assert!(kernel.processes().iter().all(|process| !process.running));
+    assert_eq!(responses(&kernel), "0\n1\n");
+    let log = log(&kernel);
+    assert!(log.contains("Invalid message: expected value at line 1 column 1\n"));
+    assert!(log.contains("Message longer than 65536 bytes dropped\n"));
+}
+
+#[test]
+fn drops_rest_of_invalid_message_written_later() {
+    let kernel = MockKernel::new();
+    add_service(&kernel, "a", "/usr/bin/a");
+    kernel.feed(KERNEL_FIFO_READ, "xx ");
+    kernel.feed(KERNEL_FIFO_READ, "{\"Stop\": \"a\"}\n");
+    kernel.feed(KERNEL_FIFO_READ, "\"List\"\n");
+
+    run(&kernel);
+
+    assert!(kernel.processes()[0].running);
+    assert_eq!(responses(&kernel).lines().count(), 1);
+    assert!(log(&kernel).contains("Invalid message: expected value at line 1 column 1\n"));
+}
+
+#[test]
+fn stops_service_with_signal() {
+    let kernel = MockKernel::new();
+    add_service_with(
+        &kernel,
+        "httpd",
+        "/usr/bin/httpd",
+        r#", "stop_signal": "SIGINT""#,
+    );
+    kernel.feed(KERNEL_FIFO_READ, r#"{"Stop": "httpd"}"#);
+
+    run(&kernel);
+
+    let process = &kernel.processes()[0];
+    assert_eq!(process.signals, [Signal::Int]);
+    assert!(!process.running);
+    assert!(log(&kernel).ends_with("httpd stopped\n"));
+}
+
+#[test]
+fn kills_service_that_does_not_stop() {
+    let kernel = MockKernel::new();
+    kernel.ignore_signal("/usr/bin/httpd", Signal::Term);
+    add_service_with(
+        &kernel,
+        "httpd",
+        "/usr/bin/httpd",
+        r#", "stop_timeout_ms": 500"#,
+    );
+    kernel.feed(KERNEL_FIFO_READ, r#"{"Stop": "httpd"}"#);
+
+    run(&kernel);
+
+    let processes = kernel.processes();
+    assert_eq!(processes.len(), 1);
+    assert_eq!(processes[0].signals, [Signal::Term, Signal::Kill]);
+    assert!(!processes[0].running);
+    let log = log(&kernel);
+    assert!(log.contains("httpd did not stop within 500 ms, killing it\n"));
+    assert!(log.ends_with("httpd stopped\n"));
+}
+
+#[test]
+fn stops_service_with_command() {
+    let kernel = MockKernel::new();
+    add_service_with(
+        &kernel,
+        "httpd",
+        "/usr/bin/httpd",
+        r#", "stop_cmd": ["/usr/bin/httpd-ctl", "stop"]"#,
+    );
+    kernel.feed(KERNEL_FIFO_READ, r#"{"Stop": "httpd"}"#);
+
+    run(&kernel);
+
+    let processes = kernel.processes();
+    assert_eq!(processes[1].path, "/usr/bin/httpd-ctl");
+    assert_eq!(processes[1].args, ["stop"]);
+    // The mock command does not stop httpd, so it is killed after the timeout
+    assert_eq!(processes[0].signals, [Signal::Kill]);
+    assert!(log(&kernel).contains("httpd did not stop within 3000 ms, killing it\n"));
+}
+
+#[test]
+fn replies_to_stop_once_service_exited() {
+    let kernel = MockKernel::new();
+    kernel.clone().mknod("/dev/reply.fifo", FIFO_DEV).unwrap();
+    kernel.ignore_signal("/usr/bin/httpd", Signal::Term);
+    add_service_with(
+        &kernel,
+        "httpd",
+        "/usr/bin/httpd",
+        r#", "stop_timeout_ms": 500"#,
+    );
+    kernel.feed(
+        FIFO,
+        r#"{"operation": {"Stop": "httpd"}, "reply": "/dev/reply.fifo", "id": 1}"#,
+    );
+
+    run(&kernel);
+
+    assert!(!kernel.processes()[0].running);
+    let reply = String::from_utf8(kernel.file("/dev/reply.fifo").unwrap()).unwrap();
+    assert_eq!(reply, "{\"id\":1,\"ok\":\"done\"}\n");
+    let log = log(&kernel);
+    assert!(log.find("killing it").unwrap() < log.find("httpd stopped").unwrap());
+}
+
+#[test]
+fn spawns_services_with_environment() {
+    let kernel = MockKernel::new();
+    kernel.add_file(
+        "/etc/environment",
+        "# Defaults\nPATH=/usr/bin\nLANG=C\n\nHOME=/\n",
+    );
+    kernel.add_file(
+        "/etc/httpd.env",
+        "export LANG=\"en_US.UTF-8\"\nPORT='8080'\n",
+    );
+    add_service_with(
+        &kernel,
+        "httpd",
+        "/usr/bin/httpd",
+        r#", "env_file": "/etc/httpd.env", "env": {"PORT": "80"}, "cwd": "/srv""#,
+    );
+
+    run(&kernel);
+
+    let env = &kernel.processes()[0].env;
+    let expected = [
+        ("PATH", "/usr/bin"),
+        ("LANG", "en_US.UTF-8"),
+        ("HOME", "/"),
+        ("PORT", "80"),
+        ("PWD", "/srv"),
+    ];
+    assert_eq!(env.len(), expected.len());
+    for (name, value) in expected {
+        assert_eq!(env[name], value);
+    }
+}
+
+#[test]
+fn does_not_start_service_with_invalid_env_file() {
+    let kernel = MockKernel::new();
+    kernel.add_file("/etc/httpd.env", "PORT=80\nlisten on 80\n");
+    add_service_with(
+        &kernel,
+        "httpd",
+        "/usr/bin/httpd",
+        r#", "env_file": "/etc/httpd.env""#,
+    );
+    add_service_with(
+        &kernel,
+        "db",
+        "/usr/bin/db",
+        r#", "env_file": "/etc/db.env""#,
+    );
+
+    run(&kernel);
+
+    assert!(kernel.processes().is_empty());
+    let log = log(&kernel);
+    assert!(log.contains("httpd: /etc/httpd.env:2: expected NAME=VALUE\n"));
+    assert!(log.contains("db: /etc/db.env: No such file or directory\n"));
+}
+
+#[test]
+fn spawns_process_with_environment() {
+    let kernel = MockKernel::new();
+    kernel.add_file("/etc/environment", "PATH=/usr/bin\n");
+    kernel.feed(
+        KERNEL_FIFO_READ,
+        r#"{"Spawn": {
+            "cmd": "/usr/bin/hello",
+            "stdin": "/dev/null",
+            "stdout": "/dev/null",
+            "stderr": "/dev/null",
+            "args": [],
+            "kern": false,
+            "env": {"GREETING": "hi"},
+            "cwd": "/tmp"
+        }}"#,
+    );
+
+    run(&kernel);
+
+    let env = &kernel.processes()[0].env;
+    assert_eq!(env.len(), 3);
+    assert_eq!(env["PATH"], "/usr/bin");
+    assert_eq!(env["GREETING"], "hi");
+    assert_eq!(env["PWD"], "/tmp");
+}
+
+// Runs `init --check`, returning the exit code and the output
+fn check(kernel: &MockKernel) -> (i32, String) {
+    let mut out = Vec::new();
+    let mut ctx = Context::new(&b""[..], &mut out, Vec::new()).with_kernel(kernel.clone());
+    let code = match init::init(["--check"], &mut ctx) {
+        Ok(()) => 0,
+        Err(e) => e.code(),
+    };
+    drop(ctx);
+    (code, String::from_utf8(out).unwrap())
+}
+
+#[test]
+fn checks_services() {
+    let kernel = MockKernel::new();
+    kernel.add_file("/usr/bin/httpd", "");
+    add_service(&kernel, "httpd", "/usr/bin/httpd");
+
+    let (code, out) = check(&kernel);
+
+    assert_eq!(code, 0);
+    assert_eq!(out, "1 service checked, 0 errors, 0 warnings\n");
+    assert!(kernel.processes().is_empty());
+    assert!(!kernel.is_fifo(FIFO));
+}
+
+#[test]
+fn reports_problems_in_services() {
+    let kernel = MockKernel::new();
+    kernel.add_file("/usr/bin/httpd", "");
+    add_service_with(
+        &kernel,
+        "httpd",
+        "/usr/bin/httpd",
+        r#", "restart_delay": 100, "stop_cmd": ["/usr/bin/httpd-ctl"]"#,
+    );
+    kernel.add_file(
+        "/etc/init.d/db",
+        "{\n  \"name\": \"db\",\n  \"restart\": \"sometimes\"\n}",
+    );
+    kernel.add_file("/etc/init.d/web", kernel.file("/etc/init.d/httpd").unwrap());
+
+    let (code, out) = check(&kernel);
+
+    assert_eq!(code, applet::EXIT_FAILURE);
+    assert_eq!(
+        out.lines().collect::<Vec<&str>>(),
+        [
+            "/etc/init.d/db:3:24: error: unknown variant `sometimes`, expected one of `never`, `on-failure`, `always`",
+            "/etc/init.d/httpd: warning: unknown field `restart_delay`",
+            "/etc/init.d/httpd: warning: executable /usr/bin/httpd-ctl: No such file or directory",
+            "/etc/init.d/web: warning: unknown field `restart_delay`",
+            "/etc/init.d/web: warning: service httpd is already defined in /etc/init.d/httpd",
+            "/etc/init.d/web: warning: executable /usr/bin/httpd-ctl: No such file or directory",
+            "2 services checked, 1 error, 5 warnings",
+        ]
+    );
+}
+
+#[test]
+fn reloads_services() {
+    let kernel = MockKernel::new();
+    for name in ["changed", "removed", "same"] {
+        add_service(&kernel, name, &format!("/usr/bin/{}", name));
+    }
+    // The files are edited by a process spawned before the reload
+    let edits = [
+        (
+            "/etc/init.d/changed",
+            Some(service_json("changed", "/usr/bin/changed2", "")),
+        ),
+        ("/etc/init.d/removed", None),
+        (
+            "/etc/init.d/added",
+            Some(service_json(
+                "added",
+                "/usr/bin/added",
+                r#", "requires": ["same"]"#,
+            )),
+        ),
+    ];
+    for (file, content) in edits {
+        kernel.write_on_spawn("/usr/bin/edit", file, content);
+    }
+    kernel.feed(
+        KERNEL_FIFO_READ,
+        r#"{"Spawn": {
+            "cmd": "/usr/bin/edit",
+            "stdin": "/dev/null",
+            "stdout": "/dev/null",
+            "stderr": "/dev/null",
+            "args": [],
+            "kern": false
+        }}"#,
+    );
+    kernel.feed(KERNEL_FIFO_READ, r#""Reload""#);
+    kernel.feed(KERNEL_FIFO_READ, r#"{"Status": "removed"}"#);
+
+    run(&kernel);
+
+    let processes = kernel.processes();
+    let paths = processes
+        .iter()
+        .map(|process| (process.path.as_str(), process.running))
+        .collect::<Vec<(&str, bool)>>();
+    assert_eq!(
+        paths,
+        [
+            ("/usr/bin/changed", false),
+            ("/usr/bin/removed", false),
+            ("/usr/bin/same", true),
+            ("/usr/bin/edit", true),
+            ("/usr/bin/added", true),
+            ("/usr/bin/changed2", true),
+        ]
+    );
+    assert!(processes[2].signals.is_empty());
+    assert_eq!(
+        responses(&kernel),
+        format!(
+            "0 {}\n1 {}\n",
+            processes[3].pid, r#"{"added":["added"],"removed":["removed"],"changed":["changed"]}"#
+        )
+    );
+    let log = log(&kernel);
+    assert!(log.contains("Added added\nRemoved removed\nChanged changed\n"));
+    assert!(log.contains("removed stopped\n"));
+    assert!(log.contains("Restarted changed\n"));
+    assert!(log.contains("Request failed: Service removed not found\n"));
+}
+
+// A service without stdout and stderr, whose output is captured by init
+fn add_logged_service(kernel: &MockKernel, name: &str, cmd: &str, fields: &str) {
+    kernel.add_file(
+        &format!("/etc/init.d/{}", name),
+        format!(
+            r#"{{"name": "{name}", "stdin": "/dev/null", "cmd": "{cmd}", "args": []{fields}}}"#
+        ),
+    );
+}
+
+#[test]
+fn keeps_running_when_log_fifo_fails() {
+    let kernel = MockKernel::new();
+    add_logged_service(&kernel, "httpd", "/usr/bin/httpd", "");
+    kernel.fail_poll("/dev/init.httpd.log.fifo");
+    kernel.feed(KERNEL_FIFO_READ, r#"{"Status": "httpd"}"#);
+
+    run(&kernel);
+
+    assert!(responses(&kernel).starts_with("0 {\"name\":\"httpd\""));
+    let log = log(&kernel);
+    assert!(log.contains("Could not poll: I/O error\n"));
+    assert!(log.contains("Could not read the output of httpd, no longer capturing it: I/O error\n"));
+}
+
+// Lines of a service log, without their timestamps
+fn log_lines(kernel: &MockKernel, path: &str) -> Vec<String> {
+    let log = String::from_utf8(kernel.file(path).unwrap()).unwrap();
+    log.lines()
+        .map(|line| {
+            let (timestamp, line) = line.split_once(' ').unwrap();
+            assert_eq!(timestamp.len(), "2026-01-31T12:00:00.250Z".len());
+            assert!(timestamp.ends_with('Z'));
+            line.to_string()
+        })
+        .collect()
+}
+
+#[test]
+fn captures_output_of_services() {
+    let kernel = MockKernel::new();
+    add_logged_service(&kernel, "httpd", "/usr/bin/httpd", "");
+    kernel.feed("/dev/init.httpd.log.fifo", "listening on :80\nGET /ind");
+    kernel.feed("/dev/init.httpd.log.fifo", "ex.html\n");
+    kernel.feed(KERNEL_FIFO_READ, r#"{"Status": "httpd"}"#);
+
+    run(&kernel);
+
+    assert!(kernel.is_fifo("/dev/init.httpd.log.fifo"));
+    assert_eq!(
+        kernel.processes()[0].redirects,
+        [
+            Redirect::Read(0, String::from("/dev/null")),
+            Redirect::Append(1, String::from("/dev/init.httpd.log.fifo")),
+            Redirect::Append(2, String::from("/dev/init.httpd.log.fifo")),
+        ]
+    );
+    assert_eq!(
+        log_lines(&kernel, "/var/log/httpd.log"),
+        ["listening on :80", "GET /index.html"]
+    );
+    assert!(responses(&kernel)
+        .contains(r#""stdout":"/var/log/httpd.log","stderr":"/var/log/httpd.log""#));
+}
+
+#[test]
+fn logs_output_left_by_exited_service() {
+    let kernel = MockKernel::new();
+    add_logged_service(&kernel, "httpd", "/usr/bin/httpd", "");
+    kernel.crash_on_spawn("/usr/bin/httpd", 1);
+    kernel.feed("/dev/init.httpd.log.fifo", "starting\n");
+    kernel.feed("/dev/init.httpd.log.fifo", "port 80 in use");
+
+    run(&kernel);
+
+    assert_eq!(
+        log_lines(&kernel, "/var/log/httpd.log"),
+        ["starting", "port 80 in use"]
+    );
+}
+
+#[test]
+fn rotates_service_logs() {
+    let kernel = MockKernel::new();
+    // Every line takes 35 bytes with its timestamp, two fit in a log
+    add_logged_service(
+        &kernel,
+        "httpd",
+        "/usr/bin/httpd",
+        r#", "log_max_bytes": 70, "log_keep": 2"#,
+    );
+    kernel.add_file("/var/log/httpd.log", "2026-01-31T12:00:00.250Z request 0\n");
+    for n in 1..=7 {
+        kernel.feed("/dev/init.httpd.log.fifo", format!("request {}\n", n));
+    }
+
+    run(&kernel);
+
+    assert_eq!(
+        log_lines(&kernel, "/var/log/httpd.log"),
+        ["request 6", "request 7"]
+    );
+    assert_eq!(
+        log_lines(&kernel, "/var/log/httpd.log.1"),
+        ["request 4", "request 5"]
+    );
+    assert_eq!(
+        log_lines(&kernel, "/var/log/httpd.log.2"),
+        ["request 2", "request 3"]
+    );
+    assert_eq!(kernel.file("/var/log/httpd.log.3"), None);
+}
+
+#[test]
+fn waits_for_oneshot_services() {
+    let kernel = MockKernel::new();
+    kernel.crash_on_spawn("/usr/bin/mount-all", 0);
+    kernel.crash_on_spawn("/usr/bin/migrate", 0);
+    add_service_with(
+        &kernel,
+        "mounts",
+        "/usr/bin/mount-all",
+        r#", "type": "oneshot", "target": "sysinit""#,
+    );
+    add_service_with(&kernel, "db", "/usr/bin/db", r#", "after": ["migrate"]"#);
+    add_service_with(
+        &kernel,
+        "migrate",
+        "/usr/bin/migrate",
+        r#", "type": "oneshot", "requires": ["mounts"]"#,
+    );
+    add_service_with(
+        &kernel,
+        "syslog",
+        "/usr/bin/syslog",
+        r#", "target": "basic""#,
+    );
+
+    run(&kernel);
+
+    assert_eq!(
+        spawned(&kernel),
+        [
+            "/usr/bin/mount-all",
+            "/usr/bin/syslog",
+            "/usr/bin/migrate",
+            "/usr/bin/db"
+        ]
+    );
+    let log = log(&kernel);
+    let lines = log
+        .lines()
+        .map(|line| line.split(" with status").next().unwrap())
+        .collect::<Vec<&str>>();
+    assert_eq!(
+        lines,
+        [
+            "mounts exited",
+            "Reached target sysinit",
+            "Reached target basic",
+            "migrate exited",
+            "Reached target default",
+            "Boot completed in 0 ms"
+        ]
+    );
+}
+
+#[test]
+fn counts_oneshot_service_exiting_with_unknown_status_as_done() {
+    let kernel = MockKernel::new();
+    kernel.hide_exit_statuses();
+    kernel.crash_on_spawn("/usr/bin/mount-all", 1);
+    add_service_with(
+        &kernel,
+        "mounts",
+        "/usr/bin/mount-all",
+        r#", "type": "oneshot", "target": "sysinit""#,
+    );
+    add_service_with(&kernel, "db", "/usr/bin/db", r#", "requires": ["mounts"]"#);
+
+    run(&kernel);
+
+    assert_eq!(spawned(&kernel), ["/usr/bin/mount-all", "/usr/bin/db"]);
+    assert!(log(&kernel).ends_with("Boot completed in 0 ms\n"));
+}
+
+#[test]
+fn does_not_reach_target_before_oneshot_services_exit() {
+    let kernel = MockKernel::new();
+    add_service_with(
+        &kernel,
+        "unpack",
+        "/usr/bin/unpack",
+        r#", "type": "oneshot", "target": "basic""#,
+    );
+    add_service(&kernel, "httpd", "/usr/bin/httpd");
+    kernel.feed(KERNEL_FIFO_READ, r#"{"Status": "httpd"}"#);
+
+    run(&kernel);
+
+    assert_eq!(spawned(&kernel), ["/usr/bin/unpack"]);
+    assert_eq!(log(&kernel), "Reached target sysinit\n");
+    assert!(responses(&kernel).contains(r#""name":"httpd","pid":null,"state":"waiting""#));
+}
+
+#[test]
+fn skips_services_requiring_failed_oneshot_service() {
+    let kernel = MockKernel::new();
+    kernel.crash_on_spawn("/usr/bin/mount-all", 1);
+    add_service_with(
+        &kernel,
+        "mounts",
+        "/usr/bin/mount-all",
+        r#", "type": "oneshot", "target": "sysinit""#,
+    );
+    add_service_with(&kernel, "db", "/usr/bin/db", r#", "requires": ["mounts"]"#);
+    add_service(&kernel, "httpd", "/usr/bin/httpd");
+
+    run(&kernel);
+
+    assert_eq!(spawned(&kernel), ["/usr/bin/mount-all", "/usr/bin/httpd"]);
+    assert!(log(&kernel).contains("db: required service mounts failed to start\n"));
+    assert!(log(&kernel).ends_with("Boot completed in 0 ms\n"));
+}
+
+// init returns once the system is shut down
+fn shut_down(kernel: &MockKernel) {
+    let mut ctx = Context::new(&b""[..], Vec::new(), Vec::new()).with_kernel(kernel.clone());
+    init::init(Vec::<String>::new(), &mut ctx).unwrap();
+}
+
+#[test]
+fn reboots_after_stopping_services_in_reverse_order() {
+    let mut kernel = MockKernel::new();
+    add_service_with(
+        &kernel,
+        "mounts",
+        "/usr/bin/mounts",
+        r#", "target": "sysinit""#,
+    );
+    add_service(&kernel, "db", "/usr/bin/db");
+    add_service_with(&kernel, "web", "/usr/bin/web", r#", "requires": ["db"]"#);
+    kernel.mount("", "/mnt/data", "fsa", 0, "").unwrap();
+    kernel.mount("", "/mnt/data/cache", "fsa", 0, "").unwrap();
+    kernel.add_file("/proc/sys/reset", "");
+    kernel.feed(KERNEL_FIFO_READ, r#"{"Shutdown": {"reboot": true}}"#);
+
+    shut_down(&kernel);
+
+    assert_eq!(responses(&kernel), "0\n");
+    let log = log(&kernel);
+    let lines = log
+        .lines()
+        .skip_while(|line| !line.starts_with("Rebooting"))
+        .map(|line| line.split(" with status").next().unwrap())
+        .collect::<Vec<&str>>();
+    assert_eq!(
+        lines,
+        [
+            "Rebooting the system",
+            "web exited",
+            "web stopped",
+            "db exited",
+            "db stopped",
+            "mounts exited",
+            "mounts stopped",
+            "Unmounted /mnt/data/cache",
+            "Unmounted /mnt/data",
+            "Resetting the system"
+        ]
+    );
+    assert_eq!(kernel.mounts().len(), 1);
+    assert_eq!(kernel.file("/proc/sys/reset").unwrap(), b"1");
+}
+
+#[test]
+fn powers_off_once_services_are_killed() {
+    let kernel = MockKernel::new();
+    add_service(&kernel, "httpd", "/usr/bin/httpd");
+    kernel.ignore_signal("/usr/bin/httpd", Signal::Term);
+    kernel.add_file("/proc/sys/reset", "");
+    kernel.feed(KERNEL_FIFO_READ, r#"{"Shutdown": {"reboot": false}}"#);
+    kernel.feed(KERNEL_FIFO_READ, r#"{"Start": "httpd"}"#);
+    kernel.feed(KERNEL_FIFO_READ, r#"{"Status": "httpd"}"#);
+
+    shut_down(&kernel);
+
+    assert_eq!(kernel.processes()[0].signals, [Signal::Term, Signal::Kill]);
+    let responses = responses(&kernel);
+    assert!(responses.starts_with("0\n2 "));
+    let log = log(&kernel);
+    assert!(log.contains("Request failed: The system is shutting down\n"));
+    assert!(log.contains("httpd did not stop within 3000 ms, killing it\n"));
+    assert!(log.ends_with("System halted\n"));
+    assert_eq!(kernel.file("/proc/sys/reset").unwrap(), b"");
+}
+
+#[test]
+fn powers_off_once_shutdown_times_out() {
+    let kernel = MockKernel::new();
+    add_service_with(
+        &kernel,
+        "httpd",
+        "/usr/bin/httpd",
+        r#", "stop_timeout_ms": 500"#,
+    );
+    kernel.fail_kill("/usr/bin/httpd");
+    kernel.add_file("/proc/sys/reset", "");
+    kernel.feed(KERNEL_FIFO_READ, r#"{"Shutdown": {"reboot": false}}"#);
+
+    shut_down(&kernel);
+
+    assert!(kernel.processes()[0].running);
+    assert_eq!(kernel.clone().clock().unwrap(), Duration::from_millis(5500));
+    let log = log(&kernel);
+    assert!(log.contains("httpd: Operation not permitted\n"));
+    assert!(log.contains("Could not kill httpd: Operation not permitted\n"));
+    assert!(
+        log.ends_with("Services still running at the end of the shutdown: httpd\nSystem halted\n")
+    );
+}
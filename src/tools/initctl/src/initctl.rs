@@ -0,0 +1,500 @@
+/*
+ * Copyright (c) 2026 Antmicro <www.antmicro.com>
+ *
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use std::io;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::time::Duration;
+
+use applet::errno::WithPath;
+use applet::kernel::{Device, Ioctl, Kernel, OpenFlags, FIFO_DEV};
+use applet::{parse_args, AppletResult, Context, Manual, EXIT_FAILURE, EXIT_USAGE};
+use clap::{Command, CommandFactory, Parser, Subcommand, ValueHint};
+use serde::Serialize;
+
+use init::protocol::{
+    Operation, Outcome, ReloadSummary, Reply, Request, Response, ServiceStatus, SpawnArgs,
+    FIFO_PATH,
+};
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Control the services of init
+///
+/// Sends requests to init through /dev/init.fifo and waits for its reply on
+/// a fifo created for the request. Services are described in a readable
+/// form, or as the JSON sent by init with --json.
+#[derive(Parser)]
+#[command(no_binary_name = true)]
+struct CliArgs {
+    /// print the replies of init as JSON
+    #[arg(long, global = true)]
+    json: bool,
+    /// seconds to wait for the reply of init, added to the stop timeout of
+    /// the service for stop
+    #[arg(
+        short,
+        long,
+        value_name = "SECONDS",
+        default_value_t = 5,
+        global = true
+    )]
+    timeout: u64,
+    #[command(subcommand)]
+    action: Action,
+}
+
+#[derive(Subcommand)]
+enum Action {
+    /// start a service
+    Start {
+        /// name of the service
+        name: String,
+    },
+    /// stop a service and wait for it to exit
+    Stop {
+        /// name of the service
+        name: String,
+    },
+    /// stop a service and start it again
+    Restart {
+        /// name of the service
+        name: String,
+    },
+    /// describe a service
+    Status {
+        /// name of the service
+        name: String,
+    },
+    /// describe every service
+    List,
+    /// read /etc/init.d again, starting, stopping and restarting the
+    /// services that changed
+    Reload,
+    /// spawn a process in the background and print its pid
+    Spawn {
+        /// file to use as the standard input
+        #[arg(long, default_value = "/dev/null", value_hint = ValueHint::FilePath)]
+        stdin: String,
+        /// file to append the standard output to
+        #[arg(long, default_value = "/dev/null", value_hint = ValueHint::FilePath)]
+        stdout: String,
+        /// file to append the standard error to
+        #[arg(long, default_value = "/dev/null", value_hint = ValueHint::FilePath)]
+        stderr: String,
+        /// variable to add to the environment of the command
+        #[arg(short, long, value_name = "NAME=VALUE", value_parser = parse_variable)]
+        env: Vec<(String, String)>,
+        /// file with variables to add to the environment of the command
+        #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+        env_file: Option<String>,
+        /// working directory of the command
+        #[arg(long, value_name = "DIR", value_hint = ValueHint::DirPath)]
+        cwd: Option<String>,
+        /// command to run and its arguments
+        #[arg(
+            value_name = "CMD",
+            required = true,
+            trailing_var_arg = true,
+            allow_hyphen_values = true,
+            value_hint = ValueHint::CommandWithArguments
+        )]
+        command: Vec<String>,
+    },
+    /// print the output of a service, or the log of init
+    Logs {
+        /// name of the service
+        name: Option<String>,
+        /// print only the last N lines
+        #[arg(short = 'n', long, value_name = "N")]
+        lines: Option<usize>,
+        /// keep printing the lines appended to the log
+        #[arg(short, long)]
+        follow: bool,
+    },
+}
+
+fn parse_variable(arg: &str) -> Result<(String, String), String> {
+    match arg.split_once('=') {
+        Some((name, value)) if !name.is_empty() => Ok((name.to_string(), value.to_string())),
+        _ => Err(String::from("expected NAME=VALUE")),
+    }
+}
+
+pub fn command() -> Command {
+    CliArgs::command()
+}
+
+pub const MANUAL: Manual = Manual {
+    exit_codes: &[
+        (0, "success"),
+        (
+            EXIT_FAILURE,
+            "the request could not be sent or init did not reply in time",
+        ),
+        (EXIT_USAGE, "invalid command line arguments"),
+    ],
+    examples: &[
+        ("initctl list", "describe every service"),
+        ("initctl restart httpd", "restart the httpd service"),
+        (
+            "initctl reload",
+            "apply the changes made to the files in /etc/init.d",
+        ),
+        (
+            "initctl --json status httpd",
+            "describe the httpd service as JSON",
+        ),
+        (
+            "initctl spawn --stdout /tmp/ls.out ls /",
+            "run ls in the background",
+        ),
+        (
+            "initctl spawn --cwd /tmp -e LANG=C make",
+            "run make in /tmp with LANG set to C",
+        ),
+        (
+            "initctl logs -n 20 -f httpd",
+            "print the last 20 lines of the output of httpd and follow it",
+        ),
+    ],
+    sections: &[],
+};
+
+const REPLY_FIFO_PREFIX: &str = "/dev/initctl";
+
+/// Interval between the checks for new lines of a followed log.
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Creates a fifo for the reply of init, under the first free name.
+fn create_reply_fifo(kernel: &mut dyn Kernel) -> io::Result<(String, Box<dyn Device>)> {
+    let mut n = 0;
+    let path = loop {
+        let path = format!("{}.{}.fifo", REPLY_FIFO_PREFIX, n);
+        match kernel.mknod(&path, FIFO_DEV) {
+            Ok(()) => break path,
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => n += 1,
+            Err(e) => return Err(e).with_path(&path),
+        }
+    };
+
+    let mut fifo = kernel.open(&path, OpenFlags::READ).with_path(&path)?;
+    // Removed once initctl exits
+    fifo.ioctl(Ioctl::FifoCloseRemove, Some(&mut 1))
+        .with_path(&path)?;
+    Ok((path, fifo))
+}
+
+/// Sends the operation to init and waits for the reply, failing with the
+/// message of init if the request failed.
+pub fn send(
+    kernel: &mut dyn Kernel,
+    operation: Operation,
+    timeout: Duration,
+) -> io::Result<Response> {
+    let (path, mut fifo) = create_reply_fifo(kernel)?;
+    let request = Request {
+        operation,
+        reply: Some(path.clone()),
+        id: None,
+    };
+    let message = serde_json::to_string(&request)? + "\n";
+    kernel
+        .open(FIFO_PATH, OpenFlags::WRITE)
+        .and_then(|mut init| init.write_all(message.as_bytes()))
+        .with_path(FIFO_PATH)?;
+
+    let no_reply = || io::Error::new(io::ErrorKind::TimedOut, "init did not reply");
+    let deadline = kernel.clock()? + timeout;
+    let mut reply = Vec::new();
+    let mut buf = [0u8; 4096];
+    while !reply.contains(&b'\n') {
+        let remaining = deadline.saturating_sub(kernel.clock()?);
+        if remaining.is_zero() {
+            return Err(no_reply());
+        }
+        if kernel.poll(&[fifo.fd()], Some(remaining))?.is_empty() {
+            continue;
+        }
+        let size = fifo.read(&mut buf).with_path(&path)?;
+        if size == 0 {
+            return Err(no_reply());
+        }
+        reply.extend_from_slice(&buf[..size]);
+    }
+
+    let reply = String::from_utf8_lossy(&reply);
+    let reply: Reply = serde_json::from_str(reply.lines().next().unwrap_or_default())
+        .map_err(|e| invalid_reply(&e.to_string()))?;
+    match reply.outcome {
+        Outcome::Ok(response) => Ok(response),
+        Outcome::Error(e) => Err(io::Error::other(e.message)),
+    }
+}
+
+fn invalid_reply(message: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Invalid reply of init: {}", message),
+    )
+}
+
+fn status_reply(response: Response) -> io::Result<ServiceStatus> {
+    match response {
+        Response::Status(status) => Ok(status),
+        _ => Err(invalid_reply("expected the status of a service")),
+    }
+}
+
+fn format_uptime(uptime_ms: Option<u64>) -> String {
+    let Some(uptime_ms) = uptime_ms else {
+        return String::from("-");
+    };
+    let seconds = uptime_ms / 1000;
+    match seconds {
+        0..=59 => format!("{}s", seconds),
+        60..=3599 => format!("{}m{}s", seconds / 60, seconds % 60),
+        _ => format!("{}h{}m", seconds / 3600, seconds / 60 % 60),
+    }
+}
+
+fn format_option<T: ToString>(value: Option<T>) -> String {
+    value.map_or_else(|| String::from("-"), |value| value.to_string())
+}
+
+fn print_status(out: &mut dyn Write, status: &ServiceStatus) -> io::Result<()> {
+    writeln!(out, "{}: {}", status.name, status.state)?;
+    writeln!(out, "  pid:       {}", format_option(status.pid))?;
+    writeln!(out, "  uptime:    {}", format_uptime(status.uptime_ms))?;
+    writeln!(out, "  restarts:  {}", status.restarts)?;
+    writeln!(out, "  last exit: {}", format_option(status.last_exit))?;
+    writeln!(out, "  command:   {}", status.command.join(" "))
+}
+
+fn print_list(out: &mut dyn Write, statuses: &[ServiceStatus]) -> io::Result<()> {
+    let width = statuses
+        .iter()
+        .map(|status| status.name.len())
+        .chain([4])
+        .max()
+        .unwrap();
+    writeln!(
+        out,
+        "{:<width$}  {:<10}  {:>5}  {:>6}  {:>8}  {:>9}",
+        "NAME", "STATE", "PID", "UPTIME", "RESTARTS", "LAST EXIT"
+    )?;
+    for status in statuses {
+        writeln!(
+            out,
+            "{:<width$}  {:<10}  {:>5}  {:>6}  {:>8}  {:>9}",
+            status.name,
+            status.state,
+            format_option(status.pid),
+            format_uptime(status.uptime_ms),
+            status.restarts,
+            format_option(status.last_exit)
+        )?;
+    }
+    Ok(())
+}
+
+fn print_reload(out: &mut dyn Write, summary: &ReloadSummary) -> io::Result<()> {
+    for (action, names) in [
+        ("added", &summary.added),
+        ("removed", &summary.removed),
+        ("changed", &summary.changed),
+    ] {
+        for name in names {
+            writeln!(out, "{} {}", action, name)?;
+        }
+    }
+    Ok(())
+}
+
+fn print_json<T: Serialize>(out: &mut dyn Write, value: &T) -> io::Result<()> {
+    serde_json::to_writer(&mut *out, value)?;
+    writeln!(out)
+}
+
+fn read_file(kernel: &mut dyn Kernel, path: &str) -> io::Result<Vec<u8>> {
+    let mut content = Vec::new();
+    kernel
+        .open(path, OpenFlags::READ)
+        .and_then(|mut file| file.read_to_end(&mut content))
+        .with_path(path)?;
+    Ok(content)
+}
+
+/// The last `count` lines of the content.
+fn last_lines(content: &[u8], count: usize) -> &[u8] {
+    if count == 0 {
+        return &[];
+    }
+    let end = content.len() - usize::from(content.ends_with(b"\n"));
+    let start = content[..end]
+        .iter()
+        .enumerate()
+        .rev()
+        .filter(|(_, byte)| **byte == b'\n')
+        .nth(count - 1)
+        .map_or(0, |(i, _)| i + 1);
+    &content[start..]
+}
+
+/// Prints the files, or their last `lines` lines, returning their sizes.
+fn print_files(
+    kernel: &mut dyn Kernel,
+    out: &mut dyn Write,
+    paths: &[String],
+    lines: Option<usize>,
+) -> io::Result<Vec<u64>> {
+    let mut sizes = Vec::new();
+    for path in paths {
+        let content = read_file(kernel, path)?;
+        match lines {
+            Some(count) => out.write_all(last_lines(&content, count))?,
+            None => out.write_all(&content)?,
+        }
+        sizes.push(content.len() as u64);
+    }
+    Ok(sizes)
+}
+
+/// Prints what is appended to the files from the given offsets on, until
+/// initctl is interrupted. A file that got shorter than its offset, e.g.
+/// once the log was rotated, is printed from the start.
+fn follow(
+    kernel: &mut dyn Kernel,
+    out: &mut dyn Write,
+    paths: &[String],
+    mut offsets: Vec<u64>,
+) -> io::Result<()> {
+    let mut appended = Vec::new();
+    loop {
+        kernel.poll(&[], Some(FOLLOW_INTERVAL))?;
+        for (path, offset) in paths.iter().zip(&mut offsets) {
+            // Opened every time, as rotation replaces the file
+            let mut file = match kernel.open(path, OpenFlags::READ) {
+                Ok(file) => file,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e).with_path(path),
+            };
+            if file.seek(SeekFrom::End(0)).with_path(path)? < *offset {
+                *offset = 0;
+            }
+            file.seek(SeekFrom::Start(*offset)).with_path(path)?;
+            appended.clear();
+            *offset += file.read_to_end(&mut appended).with_path(path)? as u64;
+            out.write_all(&appended)?;
+            out.flush()?;
+        }
+    }
+}
+
+pub fn initctl(
+    args: impl IntoIterator<Item = impl Into<String>>,
+    ctx: &mut Context,
+) -> AppletResult {
+    let cli: CliArgs = parse_args(args, ctx)?;
+    let timeout = Duration::from_secs(cli.timeout);
+    let kernel = ctx.kernel.as_mut();
+    let out = ctx.stdout.as_mut();
+
+    match cli.action {
+        Action::Start { name } => {
+            send(kernel, Operation::Start(name), timeout)?;
+        }
+        Action::Stop { name } => {
+            // init replies once the service exited, which can take its whole
+            // stop timeout before it is killed
+            let status = status_reply(send(kernel, Operation::Status(name.clone()), timeout)?)?;
+            let stop_timeout = Duration::from_millis(status.stop_timeout_ms);
+            send(kernel, Operation::Stop(name), timeout + stop_timeout)?;
+        }
+        Action::Restart { name } => {
+            send(kernel, Operation::Restart(name), timeout)?;
+        }
+        Action::Status { name } => {
+            let status = status_reply(send(kernel, Operation::Status(name), timeout)?)?;
+            if cli.json {
+                print_json(out, &status)?;
+            } else {
+                print_status(out, &status)?;
+            }
+        }
+        Action::List => {
+            let Response::List(statuses) = send(kernel, Operation::List, timeout)? else {
+                return Err(invalid_reply("expected a list of services").into());
+            };
+            if cli.json {
+                print_json(out, &statuses)?;
+            } else {
+                print_list(out, &statuses)?;
+            }
+        }
+        Action::Reload => {
+            let Response::Reloaded(summary) = send(kernel, Operation::Reload, timeout)? else {
+                return Err(invalid_reply("expected the changes of the reload").into());
+            };
+            if cli.json {
+                print_json(out, &summary)?;
+            } else {
+                print_reload(out, &summary)?;
+            }
+        }
+        Action::Spawn {
+            stdin,
+            stdout,
+            stderr,
+            env,
+            env_file,
+            cwd,
+            mut command,
+        } => {
+            let cmd = command.remove(0);
+            let operation = Operation::Spawn(SpawnArgs {
+                cmd,
+                stdin: Some(stdin),
+                stdout: Some(stdout),
+                stderr: Some(stderr),
+                args: command,
+                kern: false,
+                env: env.into_iter().collect(),
+                env_file,
+                cwd,
+            });
+            let Response::Pid(pid) = send(kernel, operation, timeout)? else {
+                return Err(invalid_reply("expected a pid").into());
+            };
+            if cli.json {
+                writeln!(out, "{{\"pid\":{}}}", pid)?;
+            } else {
+                writeln!(out, "{}", pid)?;
+            }
+        }
+        Action::Logs {
+            name,
+            lines,
+            follow: following,
+        } => {
+            let paths = match name {
+                Some(name) => {
+                    let status = status_reply(send(kernel, Operation::Status(name), timeout)?)?;
+                    if status.stderr == status.stdout {
+                        vec![status.stdout]
+                    } else {
+                        vec![status.stdout, status.stderr]
+                    }
+                }
+                None => vec![init::LOG_PATH.to_string()],
+            };
+            let sizes = print_files(kernel, out, &paths, lines)?;
+            if following {
+                follow(kernel, out, &paths, sizes)?;
+            }
+        }
+    }
+    Ok(())
+}
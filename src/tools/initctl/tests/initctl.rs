@@ -0,0 +1,304 @@
+/*
+ * Copyright (c) 2026 Antmicro <www.antmicro.com>
+ *
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::io;
+use std::io::Write;
+use std::rc::Rc;
+use std::time::Duration;
+
+use applet::kernel::{Kernel, FIFO_DEV};
+use applet::Context;
+use mock_kernel::MockKernel;
+
+const FIFO: &str = "/dev/init.fifo";
+const REPLY_FIFO: &str = "/dev/initctl.0.fifo";
+
+const STATUS: &str = r#"{"name":"httpd","pid":3,"state":"running","uptime_ms":75000,"restarts":1,"last_exit":1,"command":["/usr/bin/httpd","--verbose"],"stdout":"/tmp/httpd.out","stderr":"/tmp/httpd.err","stop_timeout_ms":3000}"#;
+
+// Returns the exit code and message of the error, and the output
+fn run(kernel: &MockKernel, args: &[&str]) -> (Option<(i32, String)>, String) {
+    let mut out = Vec::new();
+    let mut ctx = Context::new(&b""[..], &mut out, Vec::new()).with_kernel(kernel.clone());
+    let error = initctl::initctl(args.iter().copied(), &mut ctx)
+        .err()
+        .map(|e| (e.code(), e.message().unwrap_or_default().to_string()));
+    drop(ctx);
+    (error, String::from_utf8(out).unwrap())
+}
+
+// Sets up the fifo of init, with `reply` written to the reply fifo
+fn init(reply: &str) -> MockKernel {
+    let kernel = MockKernel::new();
+    kernel.clone().mknod(FIFO, FIFO_DEV).unwrap();
+    kernel.feed(REPLY_FIFO, reply);
+    kernel
+}
+
+fn requests(kernel: &MockKernel) -> String {
+    String::from_utf8(kernel.file(FIFO).unwrap()).unwrap()
+}
+
+#[test]
+fn sends_requests() {
+    let kernel = init("{\"ok\":\"done\"}\n");
+
+    let (error, out) = run(&kernel, &["restart", "httpd"]);
+
+    assert_eq!(error, None);
+    assert_eq!(out, "");
+    assert_eq!(
+        requests(&kernel),
+        "{\"operation\":{\"Restart\":\"httpd\"},\"reply\":\"/dev/initctl.0.fifo\"}\n"
+    );
+}
+
+#[test]
+fn uses_free_reply_fifo() {
+    let kernel = init("");
+    kernel.clone().mknod(REPLY_FIFO, FIFO_DEV).unwrap();
+    kernel.feed("/dev/initctl.1.fifo", "{\"ok\":\"done\"}\n");
+
+    let (error, _) = run(&kernel, &["start", "httpd"]);
+
+    assert_eq!(error, None);
+    assert!(requests(&kernel).contains(r#""reply":"/dev/initctl.1.fifo""#));
+}
+
+#[test]
+fn stops_service() {
+    let kernel = init(&format!("{{\"ok\":{{\"status\":{}}}}}\n", STATUS));
+    kernel.feed("/dev/initctl.1.fifo", "{\"ok\":\"done\"}\n");
+
+    let (error, _) = run(&kernel, &["stop", "httpd"]);
+
+    assert_eq!(error, None);
+    assert!(requests(&kernel)
+        .ends_with("{\"operation\":{\"Stop\":\"httpd\"},\"reply\":\"/dev/initctl.1.fifo\"}\n"));
+}
+
+#[test]
+fn waits_for_stop_timeout_of_service() {
+    let kernel = init(&format!("{{\"ok\":{{\"status\":{}}}}}\n", STATUS));
+
+    let (error, _) = run(&kernel, &["stop", "httpd", "--timeout", "1"]);
+
+    assert_eq!(error, Some((1, String::from("init did not reply"))));
+    assert_eq!(kernel.clone().clock().unwrap(), Duration::from_secs(4));
+}
+
+#[test]
+fn prints_status() {
+    let kernel = init(&format!("{{\"ok\":{{\"status\":{}}}}}\n", STATUS));
+
+    let (error, out) = run(&kernel, &["status", "httpd"]);
+
+    assert_eq!(error, None);
+    assert_eq!(
+        out,
+        "httpd: running\n\
+         \x20 pid:       3\n\
+         \x20 uptime:    1m15s\n\
+         \x20 restarts:  1\n\
+         \x20 last exit: 1\n\
+         \x20 command:   /usr/bin/httpd --verbose\n"
+    );
+    assert!(requests(&kernel).starts_with(r#"{"operation":{"Status":"httpd"}"#));
+}
+
+#[test]
+fn prints_json() {
+    let kernel = init(&format!("{{\"ok\":{{\"status\":{}}}}}\n", STATUS));
+
+    let (error, out) = run(&kernel, &["status", "--json", "httpd"]);
+
+    assert_eq!(error, None);
+    assert_eq!(out, format!("{}\n", STATUS));
+}
+
+#[test]
+fn lists_services() {
+    let kernel = init(&format!(
+        "{{\"ok\":{{\"list\":[{},{}]}}}}\n",
+        STATUS,
+        r#"{"name":"db","pid":null,"state":"failed","uptime_ms":null,"restarts":0,"last_exit":2,"command":["/usr/bin/db"],"stdout":"/dev/null","stderr":"/dev/null","stop_timeout_ms":3000}"#
+    ));
+
+    let (error, out) = run(&kernel, &["list"]);
+
+    assert_eq!(error, None);
+    assert_eq!(
+        out,
+        "NAME   STATE         PID  UPTIME  RESTARTS  LAST EXIT\n\
+         httpd  running         3   1m15s         1          1\n\
+         db     failed          -       -         0          2\n"
+    );
+    assert!(requests(&kernel).starts_with(r#"{"operation":"List""#));
+}
+
+#[test]
+fn spawns_process() {
+    let kernel = init("{\"ok\":{\"pid\":42}}\n");
+
+    let (error, out) = run(
+        &kernel,
+        &["spawn", "--stdout", "/tmp/ls.out", "ls", "-l", "/"],
+    );
+
+    assert_eq!(error, None);
+    assert_eq!(out, "42\n");
+    assert!(requests(&kernel).starts_with(
+        r#"{"operation":{"Spawn":{"cmd":"ls","stdin":"/dev/null","stdout":"/tmp/ls.out","stderr":"/dev/null","args":["-l","/"],"kern":false}}"#
+    ));
+}
+
+#[test]
+fn spawns_process_with_environment() {
+    let kernel = init("{\"ok\":{\"pid\":42}}\n");
+
+    let (error, _) = run(
+        &kernel,
+        &["spawn", "--cwd", "/tmp", "-e", "LANG=C", "make", "-e"],
+    );
+
+    assert_eq!(error, None);
+    assert!(requests(&kernel)
+        .contains(r#""args":["-e"],"kern":false,"env":{"LANG":"C"},"cwd":"/tmp"}"#));
+
+    let (error, _) = run(&kernel, &["spawn", "-e", "LANG", "make"]);
+
+    assert_eq!(error.unwrap().0, applet::EXIT_USAGE);
+}
+
+#[test]
+fn reloads_services() {
+    let kernel = init(
+        "{\"ok\":{\"reloaded\":{\"added\":[\"db\"],\"removed\":[],\"changed\":[\"httpd\",\"web\"]}}}\n",
+    );
+
+    let (error, out) = run(&kernel, &["reload"]);
+
+    assert_eq!(error, None);
+    assert_eq!(out, "added db\nchanged httpd\nchanged web\n");
+    assert!(requests(&kernel).starts_with(r#"{"operation":"Reload","#));
+}
+
+#[test]
+fn prints_logs() {
+    let kernel = init(&format!("{{\"ok\":{{\"status\":{}}}}}\n", STATUS));
+    kernel.add_file("/tmp/httpd.out", "listening\n");
+    kernel.add_file("/tmp/httpd.err", "error\n");
+    kernel.add_file("/tmp/init.log", "httpd exited\n");
+
+    let (error, out) = run(&kernel, &["logs", "httpd"]);
+
+    assert_eq!(error, None);
+    assert_eq!(out, "listening\nerror\n");
+
+    let (error, out) = run(&kernel, &["logs"]);
+
+    assert_eq!(error, None);
+    assert_eq!(out, "httpd exited\n");
+}
+
+#[test]
+fn prints_last_lines_of_logs() {
+    let kernel = init(&format!("{{\"ok\":{{\"status\":{}}}}}\n", STATUS));
+    kernel.add_file("/tmp/httpd.out", "starting\nlistening\nGET /\n");
+    kernel.add_file("/tmp/httpd.err", "warning\nerror");
+    kernel.add_file("/tmp/init.log", "httpd exited\n");
+
+    let (error, out) = run(&kernel, &["logs", "-n", "2", "httpd"]);
+
+    assert_eq!(error, None);
+    assert_eq!(out, "listening\nGET /\nwarning\nerror");
+
+    let (error, out) = run(&kernel, &["logs", "--lines", "0"]);
+
+    assert_eq!(error, None);
+    assert_eq!(out, "");
+}
+
+// Output that runs the next step on every flush, and fails once there
+// are no steps left to stop following
+struct Script {
+    out: Rc<RefCell<Vec<u8>>>,
+    steps: VecDeque<Box<dyn FnOnce()>>,
+}
+
+impl Write for Script {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.out.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let step = self
+            .steps
+            .pop_front()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::BrokenPipe, "done"))?;
+        step();
+        Ok(())
+    }
+}
+
+#[test]
+fn follows_appended_and_rotated_logs() {
+    let kernel = MockKernel::new();
+    kernel.add_file("/tmp/init.log", "first\n");
+    let (appending, rotating) = (kernel.clone(), kernel.clone());
+    let out = Rc::new(RefCell::new(Vec::new()));
+    let script = Script {
+        out: out.clone(),
+        steps: VecDeque::from([
+            Box::new(move || appending.feed("/tmp/init.log", "second\n")) as Box<dyn FnOnce()>,
+            Box::new(|| {}),
+            Box::new(move || rotating.add_file("/tmp/init.log", "third\n")),
+        ]),
+    };
+    let mut ctx = Context::new(&b""[..], script, Vec::new()).with_kernel(kernel.clone());
+
+    let error = initctl::initctl(["logs", "-f"], &mut ctx).unwrap_err();
+
+    assert_eq!(error.message(), Some("done"));
+    assert_eq!(
+        String::from_utf8(out.take()).unwrap(),
+        "first\nsecond\nthird\n"
+    );
+}
+
+#[test]
+fn fails_with_error_of_init() {
+    let kernel =
+        init("{\"error\":{\"code\":\"not-found\",\"message\":\"Service httpd not found\"}}\n");
+
+    let (error, out) = run(&kernel, &["status", "httpd"]);
+
+    assert_eq!(error, Some((1, String::from("Service httpd not found"))));
+    assert_eq!(out, "");
+}
+
+#[test]
+fn fails_without_reply() {
+    let kernel = init("");
+
+    let (error, out) = run(&kernel, &["start", "httpd", "--timeout", "1"]);
+
+    assert_eq!(error, Some((1, String::from("init did not reply"))));
+    assert_eq!(out, "");
+}
+
+#[test]
+fn fails_without_init() {
+    let kernel = MockKernel::new();
+
+    let (error, _) = run(&kernel, &["list"]);
+
+    assert_eq!(
+        error,
+        Some((1, String::from("/dev/init.fifo: No such file or directory")))
+    );
+}
@@ -4,23 +4,42 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 
-use std::env;
-use std::io;
+use applet::errno::WithPath;
+use applet::kernel::Signal;
+use applet::{parse_args, AppletResult, Context, Manual, EXIT_FAILURE, EXIT_USAGE};
+use clap::{Command, CommandFactory, Parser};
 
-use clap::Parser;
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// Terminate a process
+///
+/// Sends the SIGKILL signal to the process, which can't be caught or
+/// ignored.
 #[derive(Parser)]
 #[command(no_binary_name = true)]
 struct CliArgs {
+    /// id of the process
     pid: i32,
 }
 
-pub fn kill(args: env::Args) -> io::Result<()> {
-    let cli = CliArgs::parse_from(args);
+pub fn command() -> Command {
+    CliArgs::command()
+}
+
+pub const MANUAL: Manual = Manual {
+    exit_codes: &[
+        (0, "success"),
+        (EXIT_FAILURE, "the process doesn't exist or can't be killed"),
+        (EXIT_USAGE, "invalid command line arguments"),
+    ],
+    examples: &[("kill 12", "kill the process with pid 12")],
+    sections: &[],
+};
+
+pub fn kill(args: impl IntoIterator<Item = impl Into<String>>, ctx: &mut Context) -> AppletResult {
+    let cli: CliArgs = parse_args(args, ctx)?;
 
-    if let Err(e) = wasi_ext_lib::kill(cli.pid, wasi_ext_lib::SIGNAL_KILL) {
-        return Err(io::Error::from_raw_os_error(e));
-    }
+    ctx.kernel.kill(cli.pid, Signal::Kill).with_path(cli.pid)?;
 
     Ok(())
 }
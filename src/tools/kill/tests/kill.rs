@@ -0,0 +1,46 @@
+/*
+ * Copyright (c) 2026 Antmicro <www.antmicro.com>
+ *
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use applet::kernel::Signal;
+use applet::Context;
+use mock_kernel::MockKernel;
+
+fn run(kernel: &MockKernel, args: &[&str]) -> Option<i32> {
+    let mut ctx = Context::new(&b""[..], Vec::new(), Vec::new()).with_kernel(kernel.clone());
+    kill::kill(args.iter().copied(), &mut ctx)
+        .err()
+        .map(|e| e.code())
+}
+
+#[test]
+fn kills_process() {
+    let kernel = MockKernel::new();
+    let pid = kernel.add_process("/usr/bin/sleep", &["100"]);
+    let other = kernel.add_process("/usr/bin/sleep", &["200"]);
+
+    assert_eq!(run(&kernel, &[&pid.to_string()]), None);
+
+    let process = kernel.process(pid).unwrap();
+    assert_eq!(process.signals, [Signal::Kill]);
+    assert!(!process.running);
+    assert!(kernel.process(other).unwrap().running);
+}
+
+#[test]
+fn fails_on_missing_process() {
+    let kernel = MockKernel::new();
+    let pid = kernel.add_process("/usr/bin/sleep", &["100"]);
+
+    assert_eq!(run(&kernel, &[&(pid + 1).to_string()]), Some(1));
+    assert!(kernel.process(pid).unwrap().signals.is_empty());
+}
+
+#[test]
+fn fails_on_invalid_pid() {
+    let kernel = MockKernel::new();
+
+    assert_eq!(run(&kernel, &["init"]), Some(2));
+}
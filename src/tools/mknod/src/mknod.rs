@@ -4,33 +4,66 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 
-use std::env;
-use std::io;
+use applet::errno::WithPath;
+use applet::kernel::mkdev;
+use applet::{parse_args, AppletError, AppletResult, Context, Manual, EXIT_FAILURE, EXIT_USAGE};
+use clap::{Command, CommandFactory, Parser, ValueHint};
 
-use clap::Parser;
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// Create a device file
+///
+/// Creates a device file with the given major and minor numbers, or a fifo
+/// when the major number is negative.
 #[derive(Parser)]
-#[clap(no_binary_name(true))]
+#[clap(no_binary_name(true), allow_negative_numbers(true))]
 struct CliArgs {
+    /// path of the new file
+    #[arg(value_hint = ValueHint::FilePath)]
     path: String,
+    /// major device number, negative to create a fifo
     maj: i32,
+    /// minor device number, required for devices
     min: Option<i32>,
 }
 
-pub fn mknod(args: env::Args) -> io::Result<()> {
-    let args = CliArgs::parse_from(args);
+pub fn command() -> Command {
+    CliArgs::command()
+}
+
+pub const MANUAL: Manual = Manual {
+    exit_codes: &[
+        (0, "success"),
+        (
+            EXIT_FAILURE,
+            "the minor number is missing or the file could not be created",
+        ),
+        (EXIT_USAGE, "invalid command line arguments"),
+    ],
+    examples: &[
+        (
+            "mknod /dev/ttyS0 4 64",
+            "create the device file of a serial port",
+        ),
+        ("mknod /tmp/pipe -1", "create a fifo"),
+    ],
+    sections: &[],
+};
+
+pub fn mknod(args: impl IntoIterator<Item = impl Into<String>>, ctx: &mut Context) -> AppletResult {
+    let args: CliArgs = parse_args(args, ctx)?;
 
     if args.maj < 0 {
-        wasi_ext_lib::mknod(&args.path, args.maj).map_err(io::Error::from_raw_os_error)?;
+        ctx.kernel
+            .mknod(&args.path, args.maj)
+            .with_path(&args.path)?;
     } else {
         if args.min.is_none() {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidInput,
-                "Missing minor number",
-            ));
+            return Err(AppletError::new(EXIT_FAILURE, "Missing minor number"));
         }
-        wasi_ext_lib::mknod(&args.path, wasi_ext_lib::mkdev(args.maj, args.min.unwrap()))
-            .map_err(io::Error::from_raw_os_error)?;
+        ctx.kernel
+            .mknod(&args.path, mkdev(args.maj, args.min.unwrap()))
+            .with_path(&args.path)?;
     }
 
     Ok(())
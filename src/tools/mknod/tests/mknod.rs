@@ -0,0 +1,55 @@
+/*
+ * Copyright (c) 2026 Antmicro <www.antmicro.com>
+ *
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use applet::kernel::mkdev;
+use applet::Context;
+use mock_kernel::MockKernel;
+
+fn run(kernel: &MockKernel, args: &[&str]) -> Result<(), String> {
+    let mut ctx = Context::new(&b""[..], Vec::new(), Vec::new()).with_kernel(kernel.clone());
+    mknod::mknod(args.iter().copied(), &mut ctx).map_err(|e| e.to_string())
+}
+
+#[test]
+fn creates_device() {
+    let kernel = MockKernel::new();
+
+    run(&kernel, &["/dev/ttyS0", "4", "64"]).unwrap();
+
+    assert_eq!(kernel.device("/dev/ttyS0"), Some(mkdev(4, 64)));
+}
+
+#[test]
+fn creates_fifo() {
+    let kernel = MockKernel::new();
+
+    run(&kernel, &["/tmp/fifo", "-1"]).unwrap();
+
+    assert!(kernel.is_fifo("/tmp/fifo"));
+}
+
+#[test]
+fn requires_minor_of_device() {
+    let kernel = MockKernel::new();
+
+    assert_eq!(
+        run(&kernel, &["/dev/ttyS0", "4"]),
+        Err(String::from("Missing minor number"))
+    );
+    assert_eq!(kernel.device("/dev/ttyS0"), None);
+}
+
+#[test]
+fn fails_on_existing_path() {
+    let kernel = MockKernel::new();
+    kernel.add_file("/dev/ttyS0", "");
+
+    assert_eq!(
+        run(&kernel, &["/dev/ttyS0", "4", "64"]),
+        Err(String::from("/dev/ttyS0: File exists"))
+    );
+    assert_eq!(kernel.file("/dev/ttyS0").unwrap(), b"");
+}
@@ -4,35 +4,78 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 
-use std::env;
-use std::fs::File;
 use std::io;
-use std::io::BufRead;
+use std::io::{BufRead, Write};
 
-use clap::Parser;
+use applet::errno::WithPath;
+use applet::kernel::OpenFlags;
+use applet::{parse_args, AppletResult, Context, Manual, EXIT_FAILURE, EXIT_USAGE};
+use clap::{Command, CommandFactory, Parser, ValueHint};
 
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+const MOUNTINFO_PATH: &str = "/proc/self/mountinfo";
+
+/// Mount a filesystem or list the mounted ones
+///
+/// Without arguments, prints the mounted filesystems from
+/// /proc/self/mountinfo. Otherwise mounts the filesystem from SOURCE at
+/// TARGET. When only one path is given, it is the mount point and the
+/// filesystem has no source.
 #[derive(Parser)]
 #[clap(no_binary_name(true))]
 struct CliArgs {
+    /// type of the filesystem
     #[arg(short, long)]
     types: Option<String>,
+    /// options passed to the filesystem
     #[arg(short, long)]
     options: Option<String>,
+    /// device or directory to mount, or the mount point without TARGET
+    #[arg(value_hint = ValueHint::AnyPath)]
     source: String,
+    /// mount point
+    #[arg(value_hint = ValueHint::DirPath)]
     target: Option<String>,
 }
 
-pub fn mount(args: env::Args) -> io::Result<()> {
-    if args.len() == 0 {
-        io::BufReader::new(File::open("/proc/self/mountinfo")?)
-            .lines()
-            .for_each(|line| {
-                println!("{}", line.unwrap());
-            });
+pub fn command() -> Command {
+    CliArgs::command()
+}
+
+pub const MANUAL: Manual = Manual {
+    exit_codes: &[
+        (0, "success"),
+        (
+            EXIT_FAILURE,
+            "the filesystem could not be mounted or listed",
+        ),
+        (EXIT_USAGE, "invalid command line arguments"),
+    ],
+    examples: &[
+        ("mount", "list the mounted filesystems"),
+        (
+            "mount -t fsa /mnt",
+            "mount a filesystem of type fsa at /mnt",
+        ),
+    ],
+    sections: &[],
+};
+
+pub fn mount(args: impl IntoIterator<Item = impl Into<String>>, ctx: &mut Context) -> AppletResult {
+    let args = args.into_iter().map(Into::into).collect::<Vec<String>>();
+    if args.is_empty() {
+        let mountinfo = ctx
+            .kernel
+            .open(MOUNTINFO_PATH, OpenFlags::READ)
+            .with_path(MOUNTINFO_PATH)?;
+        for line in io::BufReader::new(mountinfo).lines() {
+            writeln!(ctx.stdout, "{}", line?)?;
+        }
         return Ok(());
     }
 
-    let args = CliArgs::parse_from(args);
+    let args: CliArgs = parse_args(args, ctx)?;
 
     let source: String;
     let target: String;
@@ -45,12 +88,14 @@ pub fn mount(args: env::Args) -> io::Result<()> {
         source = String::from("");
     }
 
-    wasi_ext_lib::mount(
-        &source,
-        &target,
-        &args.types.unwrap_or("".to_string()),
-        0u64,
-        &args.options.unwrap_or("".to_string()),
-    )
-    .map_err(io::Error::from_raw_os_error)
+    ctx.kernel
+        .mount(
+            &source,
+            &target,
+            &args.types.unwrap_or("".to_string()),
+            0u64,
+            &args.options.unwrap_or("".to_string()),
+        )
+        .with_path(&target)?;
+    Ok(())
 }
@@ -0,0 +1,73 @@
+/*
+ * Copyright (c) 2026 Antmicro <www.antmicro.com>
+ *
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use applet::Context;
+use mock_kernel::{MockKernel, Mount};
+
+fn run(kernel: &MockKernel, args: &[&str]) -> (Option<i32>, String) {
+    let mut out = Vec::new();
+    let mut ctx = Context::new(&b""[..], &mut out, Vec::new()).with_kernel(kernel.clone());
+    let code = mount::mount(args.iter().copied(), &mut ctx)
+        .err()
+        .map(|e| e.code());
+    drop(ctx);
+    (code, String::from_utf8(out).unwrap())
+}
+
+#[test]
+fn mounts_with_type_and_options() {
+    let kernel = MockKernel::new();
+
+    let (code, _) = run(&kernel, &["-t", "vfs", "-o", "ro", "none", "/mnt"]);
+
+    assert_eq!(code, None);
+    assert_eq!(
+        kernel.mounts().last(),
+        Some(&Mount {
+            source: String::from("none"),
+            target: String::from("/mnt"),
+            fstype: String::from("vfs"),
+            data: String::from("ro"),
+        })
+    );
+}
+
+#[test]
+fn mounts_without_source() {
+    let kernel = MockKernel::new();
+
+    let (code, _) = run(&kernel, &["-t", "fsa", "/mnt"]);
+
+    assert_eq!(code, None);
+    let mount = kernel.mounts().pop().unwrap();
+    assert_eq!(mount.source, "");
+    assert_eq!(mount.target, "/mnt");
+}
+
+#[test]
+fn lists_mounts() {
+    let kernel = MockKernel::new();
+    run(&kernel, &["-t", "vfs", "/mnt"]);
+
+    let (code, out) = run(&kernel, &[]);
+
+    assert_eq!(code, None);
+    let mount_points = out
+        .lines()
+        .map(|line| line.split(' ').next().unwrap())
+        .collect::<Vec<&str>>();
+    assert_eq!(mount_points, ["/", "/mnt"]);
+}
+
+#[test]
+fn fails_on_busy_target() {
+    let kernel = MockKernel::new();
+
+    let (code, _) = run(&kernel, &["-t", "vfs", "/"]);
+
+    assert_eq!(code, Some(1));
+    assert_eq!(kernel.mounts().len(), 1);
+}
@@ -0,0 +1,84 @@
+/*
+ * Copyright (c) 2026 Antmicro <www.antmicro.com>
+ *
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use std::time::Duration;
+
+use applet::{parse_args, AppletResult, Context, Manual, EXIT_FAILURE, EXIT_USAGE};
+use clap::{Command, CommandFactory, Parser};
+use init::protocol::Operation;
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Time init has to accept the request. It replies before stopping the
+/// services, so this doesn't depend on their stop timeouts.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Power off the system
+///
+/// Asks init to stop every service, the services depending on others
+/// first, and to unmount the filesystems. The system is then left halted.
+#[derive(Parser)]
+#[command(no_binary_name = true)]
+struct PoweroffArgs {}
+
+/// Reboot the system
+///
+/// Asks init to stop every service, the services depending on others
+/// first, and to unmount the filesystems. The system is then reset.
+#[derive(Parser)]
+#[command(name = "reboot", no_binary_name = true)]
+struct RebootArgs {}
+
+/// Command line interface of the poweroff applet.
+pub fn command() -> Command {
+    PoweroffArgs::command()
+}
+
+/// Command line interface of the reboot applet.
+pub fn reboot_command() -> Command {
+    RebootArgs::command()
+}
+
+pub const MANUAL: Manual = Manual {
+    exit_codes: &[
+        (0, "success, init is shutting down the system"),
+        (EXIT_FAILURE, "init did not accept the request"),
+        (EXIT_USAGE, "invalid command line arguments"),
+    ],
+    examples: &[("poweroff", "stop every service and halt the system")],
+    sections: &[],
+};
+
+pub const REBOOT_MANUAL: Manual = Manual {
+    exit_codes: &[
+        (0, "success, init is rebooting the system"),
+        (EXIT_FAILURE, "init did not accept the request"),
+        (EXIT_USAGE, "invalid command line arguments"),
+    ],
+    examples: &[("reboot", "stop every service and reset the system")],
+    sections: &[],
+};
+
+fn shutdown(ctx: &mut Context, reboot: bool) -> AppletResult {
+    initctl::send(ctx.kernel.as_mut(), Operation::Shutdown { reboot }, TIMEOUT)?;
+    Ok(())
+}
+
+pub fn poweroff(
+    args: impl IntoIterator<Item = impl Into<String>>,
+    ctx: &mut Context,
+) -> AppletResult {
+    let _: PoweroffArgs = parse_args(args, ctx)?;
+    shutdown(ctx, false)
+}
+
+pub fn reboot(
+    args: impl IntoIterator<Item = impl Into<String>>,
+    ctx: &mut Context,
+) -> AppletResult {
+    let _: RebootArgs = parse_args(args, ctx)?;
+    shutdown(ctx, true)
+}
@@ -0,0 +1,76 @@
+/*
+ * Copyright (c) 2026 Antmicro <www.antmicro.com>
+ *
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use applet::kernel::{Kernel, FIFO_DEV};
+use applet::{AppletResult, Context};
+use mock_kernel::MockKernel;
+
+const FIFO: &str = "/dev/init.fifo";
+const REPLY_FIFO: &str = "/dev/initctl.0.fifo";
+
+type Main = fn(Vec<String>, &mut Context) -> AppletResult;
+
+// Returns the exit code and message of the error
+fn run(kernel: &MockKernel, main: Main) -> Option<(i32, String)> {
+    let mut ctx = Context::new(&b""[..], Vec::new(), Vec::new()).with_kernel(kernel.clone());
+    main(Vec::new(), &mut ctx)
+        .err()
+        .map(|e| (e.code(), e.message().unwrap_or_default().to_string()))
+}
+
+// Sets up the fifo of init, with `reply` written to the reply fifo
+fn init(reply: &str) -> MockKernel {
+    let kernel = MockKernel::new();
+    kernel.clone().mknod(FIFO, FIFO_DEV).unwrap();
+    kernel.feed(REPLY_FIFO, reply);
+    kernel
+}
+
+fn requests(kernel: &MockKernel) -> String {
+    String::from_utf8(kernel.file(FIFO).unwrap()).unwrap()
+}
+
+#[test]
+fn sends_shutdown_requests() {
+    for (main, reboot) in [
+        (poweroff::poweroff as Main, false),
+        (poweroff::reboot, true),
+    ] {
+        let kernel = init("{\"ok\":\"done\"}\n");
+
+        assert_eq!(run(&kernel, main), None);
+        assert_eq!(
+            requests(&kernel),
+            format!(
+                "{{\"operation\":{{\"Shutdown\":{{\"reboot\":{}}}}},\
+                 \"reply\":\"/dev/initctl.0.fifo\"}}\n",
+                reboot
+            )
+        );
+    }
+}
+
+#[test]
+fn fails_with_error_of_init() {
+    let kernel = init(
+        "{\"error\":{\"code\":\"shutting-down\",\"message\":\"The system is shutting down\"}}\n",
+    );
+
+    assert_eq!(
+        run(&kernel, poweroff::reboot),
+        Some((1, String::from("The system is shutting down")))
+    );
+}
+
+#[test]
+fn fails_without_init() {
+    let kernel = MockKernel::new();
+
+    assert_eq!(
+        run(&kernel, poweroff::poweroff),
+        Some((1, String::from("/dev/init.fifo: No such file or directory")))
+    );
+}
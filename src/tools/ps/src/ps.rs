@@ -4,9 +4,43 @@
 * SPDX-License-Identifier: Apache-2.0
 */
 
-use std::{env::Args, fs, io};
+use std::{fs, io::Write};
+
+use applet::errno::WithPath;
+use applet::{parse_args, AppletResult, Context, Manual, EXIT_FAILURE, EXIT_USAGE};
+use clap::{Command, CommandFactory, Parser};
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// List running processes
+///
+/// Prints the id, terminal, cpu time and command of every process found in
+/// /proc.
+#[derive(Parser)]
+#[command(no_binary_name = true)]
+struct CliArgs {}
+
+pub fn command() -> Command {
+    CliArgs::command()
+}
+
+pub const MANUAL: Manual = Manual {
+    exit_codes: &[
+        (0, "success"),
+        (EXIT_FAILURE, "/proc could not be read"),
+        (EXIT_USAGE, "invalid command line arguments"),
+    ],
+    examples: &[("ps", "list the processes")],
+    sections: &[],
+};
 
 const MAJ_HTERM: u32 = 1;
+#[cfg(not(target_os = "wasi"))]
+const MAJ_PTS: u32 = 136;
+// Linux reports the cpu time in clock ticks, USER_HZ is 100 on every
+// architecture we build for
+#[cfg(not(target_os = "wasi"))]
+const TICKS_PER_SECOND: u64 = 100;
 
 fn format_time(ms: u64) -> String {
     let total_seconds = ms / 1000;
@@ -23,10 +57,12 @@ fn format_time(ms: u64) -> String {
     }
 }
 
-pub fn ps(_args: Args) -> io::Result<()> {
-    println!("    PID TTY          TIME CMD");
+pub fn ps(args: impl IntoIterator<Item = impl Into<String>>, ctx: &mut Context) -> AppletResult {
+    let _: CliArgs = parse_args(args, ctx)?;
+    writeln!(ctx.stdout, "    PID TTY          TIME CMD")?;
 
-    fs::read_dir("/proc")?
+    let procs = fs::read_dir("/proc")
+        .with_path("/proc")?
         .filter_map(|entry| entry.ok())
         .filter(|entry| {
             entry.path().is_dir()
@@ -35,36 +71,54 @@ pub fn ps(_args: Args) -> io::Result<()> {
                     .to_str()
                     .map(|s| s.parse::<u32>().is_ok())
                     .unwrap_or(false)
-        })
-        .for_each(|proc| {
-            let stat = match fs::read_to_string(proc.path().join("stat")) {
-                Ok(stat) => stat,
-                Err(_) => return,
-            };
+        });
 
-            let data: Vec<&str> = stat.split(" ").collect();
+    for proc in procs {
+        let stat = match fs::read_to_string(proc.path().join("stat")) {
+            Ok(stat) => stat,
+            Err(_) => continue,
+        };
 
-            let pid = data[0];
-            let cmd = data[1].trim_matches(|c| c == '(' || c == ')');
+        // The command is in parentheses and may contain spaces itself
+        let (pid, cmd, data) = match (stat.find(" ("), stat.rfind(") ")) {
+            (Some(start), Some(end)) if start < end => (
+                &stat[..start],
+                &stat[start + 2..end],
+                stat[end + 2..].split(' ').collect::<Vec<&str>>(),
+            ),
+            _ => continue,
+        };
 
-            let tty = data[6].parse::<u32>().unwrap_or_default();
+        // Fields after the command, starting from the process state
+        let tty = data
+            .get(4)
+            .and_then(|tty| tty.parse::<u32>().ok())
+            .unwrap_or_default();
 
-            let time = match data[13].parse::<u64>() {
-                Ok(time) => format_time(time),
-                Err(_) => String::from("--:--:--"),
-            };
+        let time = match data.get(11).map(|time| time.parse::<u64>()) {
+            #[cfg(target_os = "wasi")]
+            Some(Ok(time)) => format_time(time),
+            #[cfg(not(target_os = "wasi"))]
+            Some(Ok(time)) => format_time(time * 1000 / TICKS_PER_SECOND),
+            _ => String::from("--:--:--"),
+        };
 
-            let tty_major = (tty >> 8) & 0xFF;
-            let tty_minor = ((tty >> 12) & 0xFFF00) | (tty & 0xFF);
+        let tty_major = (tty >> 8) & 0xFF;
+        let tty_minor = ((tty >> 12) & 0xFFF00) | (tty & 0xFF);
 
-            let tty_human = if tty_major == MAJ_HTERM {
-                format!("ttyH{}", tty_minor)
-            } else {
-                String::from("?")
-            };
+        let tty_human = match tty_major {
+            MAJ_HTERM => format!("ttyH{}", tty_minor),
+            #[cfg(not(target_os = "wasi"))]
+            MAJ_PTS => format!("pts/{}", tty_minor),
+            _ => String::from("?"),
+        };
 
-            println!("{:>7} {:<6} {:>10} {}", pid, tty_human, time, cmd);
-        });
+        writeln!(
+            ctx.stdout,
+            "{:>7} {:<6} {:>10} {}",
+            pid, tty_human, time, cmd
+        )?;
+    }
 
     Ok(())
 }
@@ -4,17 +4,59 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 
+#[cfg(target_os = "wasi")]
 use std::collections::HashMap;
-use std::env::Args;
+#[cfg(target_os = "wasi")]
 use std::fs;
+#[cfg(target_os = "wasi")]
 use std::io;
+#[cfg(target_os = "wasi")]
+use std::io::Write;
+#[cfg(target_os = "wasi")]
 use std::path::PathBuf;
 
+#[cfg(target_os = "wasi")]
+use applet::errno::{self, WithPath};
+#[cfg(not(target_os = "wasi"))]
+use applet::AppletError;
+use applet::{parse_args, AppletResult, Context, Manual, EXIT_FAILURE, EXIT_USAGE};
+use clap::{Command, CommandFactory, Parser};
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Remove every file from the filesystem
+///
+/// Unmounts all filesystems, then removes every file and directory, printing
+/// their paths. The filesystem is set up from scratch on the next start.
+/// This can't be undone.
+#[derive(Parser)]
+#[command(no_binary_name = true)]
+struct CliArgs {}
+
+pub fn command() -> Command {
+    CliArgs::command()
+}
+
+pub const MANUAL: Manual = Manual {
+    exit_codes: &[
+        (0, "success, files that could not be removed are reported"),
+        (EXIT_FAILURE, "the filesystem could not be traversed"),
+        (EXIT_USAGE, "invalid command line arguments"),
+    ],
+    examples: &[("purge", "wipe the filesystem")],
+    sections: &[],
+};
+
+#[cfg(target_os = "wasi")]
 fn traverse(path: &PathBuf, paths: &mut Vec<PathBuf>) -> io::Result<()> {
     if let Ok(a) = fs::read_dir(path) {
         for entry in a {
-            let entry = entry?;
-            if entry.file_type()?.is_dir() {
+            let entry = entry.with_path(path.display())?;
+            if entry
+                .file_type()
+                .with_path(entry.path().display())?
+                .is_dir()
+            {
                 traverse(&entry.path(), paths)?;
             }
             paths.push(entry.path());
@@ -23,28 +65,38 @@ fn traverse(path: &PathBuf, paths: &mut Vec<PathBuf>) -> io::Result<()> {
     Ok(())
 }
 
-pub fn purge(mut _args: Args) -> io::Result<()> {
+#[cfg(target_os = "wasi")]
+pub fn purge(args: impl IntoIterator<Item = impl Into<String>>, ctx: &mut Context) -> AppletResult {
+    let _: CliArgs = parse_args(args, ctx)?;
     // remove all mounting points before purging
     wasi_ext_lib::spawn("/usr/bin/umount", &["-a"], &HashMap::new(), false, &[]).unwrap();
 
-    println!("Removing /filesystem-initiated");
+    writeln!(ctx.stdout, "Removing /filesystem-initiated")?;
     let _ = fs::remove_file("/filesystem-initiated");
 
-    println!("Starting purge...");
+    writeln!(ctx.stdout, "Starting purge...")?;
     let mut files: Vec<PathBuf> = vec![];
     traverse(&PathBuf::from("/"), &mut files)?;
 
     for path_obj in files {
-        println!("Removing {}", &path_obj.display());
+        writeln!(ctx.stdout, "Removing {}", &path_obj.display())?;
         if let Err(e) = if path_obj.is_dir() {
             fs::remove_dir(&path_obj)
         } else {
             fs::remove_file(&path_obj)
-        } {
-            eprintln!("Could not remove {}: {:?}", &path_obj.display(), e);
+        }
+        .with_path(path_obj.display())
+        {
+            writeln!(ctx.stderr, "purge: {}", errno::describe(&e))?;
         }
     }
-    #[cfg(target_os = "wasi")]
     let _ = wasi_ext_lib::clean_inodes();
     Ok(())
 }
+
+// Wiping the filesystem is only meant for the browser runtime, never the host
+#[cfg(not(target_os = "wasi"))]
+pub fn purge(args: impl IntoIterator<Item = impl Into<String>>, ctx: &mut Context) -> AppletResult {
+    let _: CliArgs = parse_args(args, ctx)?;
+    Err(AppletError::unsupported())
+}
@@ -4,17 +4,56 @@
 * SPDX-License-Identifier: Apache-2.0
 */
 
-use std::{
-    env::Args,
-    fs::OpenOptions,
-    io::{self, Write},
+#[cfg(target_os = "wasi")]
+use std::{fs::OpenOptions, io::Write};
+
+#[cfg(target_os = "wasi")]
+use applet::errno::WithPath;
+#[cfg(not(target_os = "wasi"))]
+use applet::AppletError;
+use applet::{parse_args, AppletResult, Context, Manual, EXIT_FAILURE, EXIT_USAGE};
+use clap::{Command, CommandFactory, Parser};
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Reset the system
+///
+/// Asks the kernel to reset the system by writing to /proc/sys/reset.
+#[derive(Parser)]
+#[command(no_binary_name = true)]
+struct CliArgs {}
+
+pub fn command() -> Command {
+    CliArgs::command()
+}
+
+pub const MANUAL: Manual = Manual {
+    exit_codes: &[
+        (0, "success"),
+        (EXIT_FAILURE, "the reset could not be requested"),
+        (EXIT_USAGE, "invalid command line arguments"),
+    ],
+    examples: &[("reset", "reset the system")],
+    sections: &[],
 };
 
+#[cfg(target_os = "wasi")]
 const RESET_PATH: &str = "/proc/sys/reset";
 
-pub fn reset(_args: Args) -> io::Result<()> {
-    let mut file = OpenOptions::new().write(true).open(RESET_PATH)?;
+#[cfg(target_os = "wasi")]
+pub fn reset(args: impl IntoIterator<Item = impl Into<String>>, ctx: &mut Context) -> AppletResult {
+    let _: CliArgs = parse_args(args, ctx)?;
+    let mut file = OpenOptions::new()
+        .write(true)
+        .open(RESET_PATH)
+        .with_path(RESET_PATH)?;
 
-    file.write_all(b"1")?;
+    file.write_all(b"1").with_path(RESET_PATH)?;
     Ok(())
 }
+
+#[cfg(not(target_os = "wasi"))]
+pub fn reset(args: impl IntoIterator<Item = impl Into<String>>, ctx: &mut Context) -> AppletResult {
+    let _: CliArgs = parse_args(args, ctx)?;
+    Err(AppletError::unsupported())
+}
@@ -4,22 +4,76 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 
-use std::env;
 use std::io;
-use std::os::fd::AsRawFd;
+use std::io::Write;
 
-use termios::tcflag_t;
-use wasi_ext_lib::{tcgetattr, tcgetwinsize, tcsetattr, termios, Fd, TcsetattrAction};
+use applet::errno::WithPath;
+use applet::kernel::termios::{self, Tcflag};
+use applet::kernel::{Kernel, STDIN_FD};
+use applet::{parse_args, AppletError, AppletResult, Context, Manual, EXIT_FAILURE, EXIT_USAGE};
+use clap::{Command, CommandFactory, Parser};
 
-fn get_size() -> io::Result<(usize, usize)> {
-    match tcgetwinsize(io::stdin().as_raw_fd() as Fd) {
-        Ok(size) => Ok((size.ws_row as usize, size.ws_col as usize)),
-        Err(e) => Err(io::Error::from_raw_os_error(e)),
-    }
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// Name of the configured terminal in error messages
+const TERMINAL: &str = "'standard input'";
+
+// Settings can also be turned off with a '-' prefix, except for size
+const SETTINGS: &[&str] = &[
+    "ignbrk", "brkint", "ignpar", "parmrk", "inpck", "istrip", "inlcr", "igncr", "icrnl", "iuclc",
+    "ixon", "ixany", "ixoff", "imaxbel", "iutf8", "opost", "olcuc", "onlcr", "ocrnl", "onocr",
+    "onlret", "ofill", "ofdel", "cs5", "cs6", "cs7", "cs8", "cstopb", "cread", "parenb", "parodd",
+    "hupcl", "clocal", "isig", "icanon", "echo", "echoe", "echok", "echonl", "noflsh", "tostop",
+    "iexten", "size",
+];
+
+/// Print or change terminal settings
+///
+/// Without arguments, prints the size of the terminal and the state of its
+/// flags. Otherwise every SETTING turns a flag on, or off when prefixed with
+/// '-'. The size setting prints the number of rows and columns instead.
+#[derive(Parser)]
+#[command(no_binary_name = true)]
+struct CliArgs {
+    /// flags to change
+    #[arg(value_name = "SETTING", allow_hyphen_values = true)]
+    settings: Vec<String>,
+}
+
+pub fn command() -> Command {
+    // The settings are only listed here, parsing accepts them with a '-'
+    // prefix too and checks them when applied
+    CliArgs::command().mut_arg("settings", |arg| arg.value_parser(SETTINGS.to_vec()))
 }
 
-fn print_termios(termios_p: &termios::termios) -> io::Result<()> {
-    fn is_flag_set(field: tcflag_t, flag: tcflag_t) -> &'static str {
+pub const MANUAL: Manual = Manual {
+    exit_codes: &[
+        (0, "success"),
+        (
+            EXIT_FAILURE,
+            "invalid setting or the terminal could not be accessed",
+        ),
+        (EXIT_USAGE, "invalid command line arguments"),
+    ],
+    examples: &[
+        ("stty", "print the terminal settings"),
+        ("stty -echo", "stop echoing the typed characters"),
+        ("stty size", "print the number of rows and columns"),
+    ],
+    sections: &[],
+};
+
+fn get_size(kernel: &mut dyn Kernel) -> io::Result<(usize, usize)> {
+    let size = kernel.tcgetwinsize(STDIN_FD).with_path(TERMINAL)?;
+    Ok((size.ws_row as usize, size.ws_col as usize))
+}
+
+fn print_termios(
+    out: &mut dyn Write,
+    kernel: &mut dyn Kernel,
+    termios_p: &termios::Termios,
+) -> io::Result<()> {
+    fn is_flag_set(field: Tcflag, flag: Tcflag) -> &'static str {
         if (field & flag) != 0 {
             ""
         } else {
@@ -27,7 +81,7 @@ fn print_termios(termios_p: &termios::termios) -> io::Result<()> {
         }
     }
 
-    fn get_csn(field: tcflag_t) -> &'static str {
+    fn get_csn(field: Tcflag) -> &'static str {
         match field & termios::CSIZE {
             termios::CS5 => "cs5",
             termios::CS6 => "cs6",
@@ -37,15 +91,16 @@ fn print_termios(termios_p: &termios::termios) -> io::Result<()> {
         }
     }
 
-    let (rows, columns) = get_size()?;
-    println!("rows {rows}; columns {columns};");
+    let (rows, columns) = get_size(kernel)?;
+    writeln!(out, "rows {rows}; columns {columns};")?;
 
     // termios.c_iflag
-    println!(
+    writeln!(
+        out,
         "{}ignbrk {}brkint {}ignpar {}parmrk {}inpck {}istrip {}inlcr {}igncr {}icrnl {}iuclc {}ixon {}ixany {}ixoff {}imaxbel {}iutf8",
         is_flag_set(termios_p.c_iflag, termios::IGNBRK),
         is_flag_set(termios_p.c_iflag, termios::BRKINT),
-        is_flag_set(termios_p.c_iflag, termios::IGNBRK),
+        is_flag_set(termios_p.c_iflag, termios::IGNPAR),
         is_flag_set(termios_p.c_iflag, termios::PARMRK),
         is_flag_set(termios_p.c_iflag, termios::INPCK),
         is_flag_set(termios_p.c_iflag, termios::ISTRIP),
@@ -58,10 +113,11 @@ fn print_termios(termios_p: &termios::termios) -> io::Result<()> {
         is_flag_set(termios_p.c_iflag, termios::IXOFF),
         is_flag_set(termios_p.c_iflag, termios::IMAXBEL),
         is_flag_set(termios_p.c_iflag, termios::IUTF8)
-    );
+    )?;
 
     // termios.c_oflag
-    println!(
+    writeln!(
+        out,
         "{}opost {}olcuc {}onlcr {}ocrnl {}onocr {}onlret {}ofill {}ofdel",
         is_flag_set(termios_p.c_oflag, termios::OPOST),
         is_flag_set(termios_p.c_oflag, termios::OLCUC),
@@ -71,10 +127,11 @@ fn print_termios(termios_p: &termios::termios) -> io::Result<()> {
         is_flag_set(termios_p.c_oflag, termios::ONLRET),
         is_flag_set(termios_p.c_oflag, termios::OFILL),
         is_flag_set(termios_p.c_oflag, termios::OFDEL)
-    );
+    )?;
 
     // termios.c_cflag
-    println!(
+    writeln!(
+        out,
         "{} {}cstopb {}cread {}parenb {}parodd {}hupcl {}clocal",
         get_csn(termios_p.c_cflag),
         is_flag_set(termios_p.c_cflag, termios::CSTOPB),
@@ -83,10 +140,11 @@ fn print_termios(termios_p: &termios::termios) -> io::Result<()> {
         is_flag_set(termios_p.c_cflag, termios::PARODD),
         is_flag_set(termios_p.c_cflag, termios::HUPCL),
         is_flag_set(termios_p.c_cflag, termios::CLOCAL)
-    );
+    )?;
 
     // termios.c_lflag
-    println!(
+    writeln!(
+        out,
         "{}isig {}icanon {}echo {}echoe {}echok {}echonl {}noflsh {}tostop {}iexten",
         is_flag_set(termios_p.c_lflag, termios::ISIG),
         is_flag_set(termios_p.c_lflag, termios::ICANON),
@@ -97,23 +155,21 @@ fn print_termios(termios_p: &termios::termios) -> io::Result<()> {
         is_flag_set(termios_p.c_lflag, termios::NOFLSH),
         is_flag_set(termios_p.c_lflag, termios::TOSTOP),
         is_flag_set(termios_p.c_lflag, termios::IEXTEN)
-    );
+    )?;
 
     Ok(())
 }
 
-pub fn stty(args: env::Args) -> io::Result<()> {
-    let mut termios = match tcgetattr(io::stdin().as_raw_fd() as Fd) {
-        Ok(termios) => termios,
-        Err(e) => return Err(io::Error::from_raw_os_error(e)),
-    };
+pub fn stty(args: impl IntoIterator<Item = impl Into<String>>, ctx: &mut Context) -> AppletResult {
+    let cli: CliArgs = parse_args(args, ctx)?;
+    let mut termios = ctx.kernel.tcgetattr(STDIN_FD).with_path(TERMINAL)?;
 
-    if args.len() == 0 {
-        print_termios(&termios)?;
+    if cli.settings.is_empty() {
+        print_termios(&mut ctx.stdout, ctx.kernel.as_mut(), &termios)?;
         return Ok(());
     }
 
-    for arg in args {
+    for arg in cli.settings {
         let on = !arg.starts_with('-');
         let arg_: &str = if on { &arg } else { &arg[1..] };
 
@@ -208,25 +264,20 @@ pub fn stty(args: env::Args) -> io::Result<()> {
                 }
             }
             "size" => {
-                let (rows, columns) = get_size()?;
-                println!("{rows} {columns}");
+                let (rows, columns) = get_size(ctx.kernel.as_mut())?;
+                writeln!(ctx.stdout, "{rows} {columns}")?;
             }
             _ => {
-                return Err(io::Error::new(
-                    io::ErrorKind::InvalidInput,
-                    "Invalid argument",
+                return Err(AppletError::new(
+                    EXIT_FAILURE,
+                    format!("invalid argument '{}'", arg),
                 ));
             }
         }
     }
 
-    if let Err(e) = tcsetattr(
-        io::stdin().as_raw_fd() as Fd,
-        TcsetattrAction::TCSANOW,
-        &termios,
-    ) {
-        Err(io::Error::from_raw_os_error(e))
-    } else {
-        Ok(())
-    }
+    ctx.kernel
+        .tcsetattr(STDIN_FD, &termios)
+        .with_path(TERMINAL)?;
+    Ok(())
 }
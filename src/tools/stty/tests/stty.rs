@@ -0,0 +1,71 @@
+/*
+ * Copyright (c) 2026 Antmicro <www.antmicro.com>
+ *
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use applet::kernel::termios;
+use applet::Context;
+use mock_kernel::MockKernel;
+
+fn run(kernel: &MockKernel, args: &[&str]) -> (Result<(), String>, String) {
+    let mut out = Vec::new();
+    let mut ctx = Context::new(&b""[..], &mut out, Vec::new()).with_kernel(kernel.clone());
+    let result = stty::stty(args.iter().copied(), &mut ctx).map_err(|e| e.to_string());
+    drop(ctx);
+    (result, String::from_utf8(out).unwrap())
+}
+
+#[test]
+fn prints_settings() {
+    let kernel = MockKernel::new();
+    kernel.set_winsize(30, 100);
+
+    let (result, out) = run(&kernel, &[]);
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(
+        out,
+        "rows 30; columns 100;\n\
+         -ignbrk -brkint -ignpar -parmrk -inpck -istrip -inlcr -igncr icrnl -iuclc ixon -ixany -ixoff imaxbel iutf8\n\
+         opost -olcuc onlcr -ocrnl -onocr -onlret -ofill -ofdel\n\
+         cs8 -cstopb cread -parenb -parodd -hupcl -clocal\n\
+         isig icanon echo echoe echok -echonl -noflsh -tostop iexten\n"
+    );
+}
+
+#[test]
+fn prints_size() {
+    let kernel = MockKernel::new();
+    kernel.set_winsize(30, 100);
+
+    let (result, out) = run(&kernel, &["size"]);
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(out, "30 100\n");
+}
+
+#[test]
+fn sets_and_clears_flags() {
+    let kernel = MockKernel::new();
+
+    let (result, _) = run(&kernel, &["-echo", "-icanon", "inlcr", "-opost"]);
+
+    assert_eq!(result, Ok(()));
+    let termios = kernel.termios();
+    assert_eq!(termios.c_lflag & (termios::ECHO | termios::ICANON), 0);
+    assert_ne!(termios.c_lflag & termios::ISIG, 0);
+    assert_ne!(termios.c_iflag & termios::INLCR, 0);
+    assert_eq!(termios.c_oflag & termios::OPOST, 0);
+}
+
+#[test]
+fn rejects_unknown_setting() {
+    let kernel = MockKernel::new();
+    let before = kernel.termios();
+
+    let (result, _) = run(&kernel, &["-echo", "raw"]);
+
+    assert_eq!(result, Err(String::from("invalid argument 'raw'")));
+    assert_eq!(kernel.termios(), before);
+}
@@ -6,25 +6,31 @@
 
 mod compression;
 
-use std::env;
 use std::fs;
 use std::io;
 use std::io::Read;
 use std::path::Path;
 
+use applet::errno::{self, WithPath};
+use applet::{parse_args, AppletError, AppletResult, Context, Manual, EXIT_FAILURE};
 use bzip2::read::BzDecoder;
 use bzip2::write::BzEncoder;
-use clap::{Args, Parser};
+use clap::{Args, Command, CommandFactory, Parser, ValueHint};
 use flate2::read::GzDecoder;
 use flate2::write::GzEncoder;
 use tar::{Archive, Builder};
 
 use compression::Compression;
 
-fn extract_stream<R: io::Read + 'static>(
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// Exit status for errors that made tar give up, as in GNU tar
+const EXIT_FATAL: i32 = 2;
+
+fn extract_stream<'a, R: io::Read + 'a>(
     stream: R,
     comp: Compression,
-) -> io::Result<Box<dyn io::Read>> {
+) -> io::Result<Box<dyn io::Read + 'a>> {
     match comp {
         Compression::Gzip => {
             let decoder = GzDecoder::new(stream);
@@ -54,7 +60,7 @@ fn extract_stream<R: io::Read + 'static>(
     }
 }
 
-fn encode_stream<R: io::Write + 'static>(stream: R, comp: Compression) -> Box<dyn io::Write> {
+fn encode_stream<'a, R: io::Write + 'a>(stream: R, comp: Compression) -> Box<dyn io::Write + 'a> {
     match comp {
         Compression::Gzip => Box::new(GzEncoder::new(stream, flate2::Compression::best())),
         Compression::Bzip => Box::new(BzEncoder::new(stream, bzip2::Compression::best())),
@@ -62,26 +68,62 @@ fn encode_stream<R: io::Write + 'static>(stream: R, comp: Compression) -> Box<dy
     }
 }
 
+/// Create or extract tar archives
+///
+/// Archives are read from stdin and written to stdout unless a file is
+/// given. The compression of an archive file is detected from its .gz or
+/// .bz2 extension when it isn't selected explicitly. Files are extracted
+/// into the current directory.
 #[derive(Parser)]
-#[command(no_binary_name = true)]
+#[command(name = "tar", no_binary_name = true)]
 struct CliArgs {
     #[command(flatten)]
     compression: CliCompression,
     #[command(flatten)]
     method: CliMethod,
-    #[arg(short, long)]
+    /// archive file
+    #[arg(short, long, value_hint = ValueHint::FilePath)]
     file: Option<String>,
+    /// list the processed files
     #[arg(short, long)]
     verbose: bool,
-    #[arg(short, long)]
+    // -f is taken by the archive path
+    /// file or directory to add to the archive, can be repeated
+    #[arg(long, value_hint = ValueHint::AnyPath)]
     files: Vec<String>,
 }
 
+pub fn command() -> Command {
+    CliArgs::command()
+}
+
+pub const MANUAL: Manual = Manual {
+    exit_codes: &[
+        (0, "success"),
+        (EXIT_FAILURE, "a file could not be read or written"),
+        (
+            EXIT_FATAL,
+            "invalid arguments, a corrupted archive or an archive that would be written to a terminal",
+        ),
+    ],
+    examples: &[
+        (
+            "tar -c -f backup.tar.gz --files docs --files notes.txt",
+            "archive docs and notes.txt with gzip compression",
+        ),
+        ("tar -x -v -f backup.tar.gz", "extract the archive, listing its files"),
+        ("tar -x -b < backup.tar.bz2", "extract a bzip2 archive read from stdin"),
+    ],
+    sections: &[],
+};
+
 #[derive(Args)]
 #[group(multiple = false)]
 struct CliCompression {
+    /// compress the archive with gzip
     #[arg(short, long)]
     gzip: bool,
+    /// compress the archive with bzip2
     #[arg(short, long)]
     bzip2: bool,
 }
@@ -89,22 +131,23 @@ struct CliCompression {
 #[derive(Args)]
 #[group(multiple = false)]
 struct CliMethod {
+    /// create a new archive
     #[arg(short, long)]
     create: bool,
+    /// extract the files from an archive
     #[arg(short = 'x', long)]
     extract: bool,
 }
 
-pub fn tar(args: env::Args) -> io::Result<()> {
-    let cli = CliArgs::parse_from(args);
-    let compression = Compression::try_from(&cli.compression).map_err(|_| {
-        io::Error::new(io::ErrorKind::InvalidInput, "Conflicting compression flags")
-    })?;
+pub fn tar(args: impl IntoIterator<Item = impl Into<String>>, ctx: &mut Context) -> AppletResult {
+    let cli: CliArgs = parse_args(args, ctx)?;
+    let compression = Compression::try_from(&cli.compression)
+        .map_err(|_| AppletError::new(EXIT_FATAL, "Conflicting compression flags"))?;
     if cli.method.extract {
-        let (input_stream, compression): (Box<dyn io::Read>, Compression) =
+        let (input_stream, compression): (Box<dyn io::Read + '_>, Compression) =
             if let Some(path) = &cli.file {
                 (
-                    Box::new(fs::File::open(path)?),
+                    Box::new(fs::File::open(path).with_path(path)?),
                     if let Compression::None = compression {
                         Compression::from_extensions(Path::new(&path))?
                     } else {
@@ -112,62 +155,51 @@ pub fn tar(args: env::Args) -> io::Result<()> {
                     },
                 )
             } else {
-                (Box::new(io::stdin()), compression)
+                (Box::new(&mut ctx.stdin), compression)
             };
-        // TODO: if stream extraction failed, the program should return 2
-        // using ? operator makes the program exit with 1
-        untar(&mut extract_stream(input_stream, compression)?, &cli)?;
+        let mut stream = extract_stream(input_stream, compression)
+            .map_err(|e| AppletError::new(EXIT_FATAL, errno::describe(&e)))?;
+        untar(&mut stream, &cli, &mut ctx.stdout)?;
     } else if cli.method.create {
-        let (output_stream, compression): (Box<dyn io::Write>, Compression) =
-            if let Some(path) = &cli.file {
-                (
-                    Box::new(fs::File::create(path)?),
-                    if let Compression::None = compression {
-                        Compression::from_extensions(Path::new(&path))?
-                    } else {
-                        compression
-                    },
-                )
-            } else {
-                #[cfg(target_os = "wasi")]
-                if let Ok(is_tty) = wasi_ext_lib::isatty(0) {
-                    if is_tty {
-                        (Box::new(io::stdout()), compression)
-                    } else {
-                        return Err(io::Error::new(
-                            io::ErrorKind::InvalidInput,
-                            "Refusing to write to stdout",
-                        ));
-                    }
-                } else {
-                    return Err(io::Error::new(
-                        io::ErrorKind::InvalidInput,
-                        "Refusing to write to stdout",
-                    ));
-                }
-                #[cfg(not(target_os = "wasi"))]
-                if atty::is(atty::Stream::Stdout) {
-                    return Err(io::Error::new(
-                        io::ErrorKind::InvalidInput,
-                        "Refusing to write to stdout",
-                    ));
+        // When the archive goes to stdout, the verbose listing goes to stderr
+        let (output_stream, compression, log): (
+            Box<dyn io::Write + '_>,
+            Compression,
+            &mut dyn io::Write,
+        ) = if let Some(path) = &cli.file {
+            (
+                Box::new(fs::File::create(path).with_path(path)?),
+                if let Compression::None = compression {
+                    Compression::from_extensions(Path::new(&path))?
                 } else {
-                    (Box::new(io::stdout()), compression)
-                }
-            };
-        create_tar(&mut encode_stream(output_stream, compression), &cli)?;
+                    compression
+                },
+                &mut ctx.stdout,
+            )
+        } else {
+            if ctx.stdout_is_terminal() {
+                return Err(AppletError::new(EXIT_FATAL, "Refusing to write to stdout"));
+            }
+            (Box::new(&mut ctx.stdout), compression, &mut ctx.stderr)
+        };
+        create_tar(&mut encode_stream(output_stream, compression), &cli, log)?;
     }
     Ok(())
 }
 
-fn untar<R: ?core::marker::Sized + io::Read>(stream: &mut R, context: &CliArgs) -> io::Result<()> {
+fn untar<R: ?core::marker::Sized + io::Read>(
+    stream: &mut R,
+    context: &CliArgs,
+    log: &mut dyn io::Write,
+) -> io::Result<()> {
     let mut archive = Archive::new(stream);
     for entry in archive.entries()? {
         let mut entry = entry?;
         if context.verbose {
-            println!("{}", entry.path().unwrap().display());
+            writeln!(log, "{}", entry.path().unwrap().display())?;
         }
-        entry.unpack_in(".")?;
+        let path = entry.path()?.display().to_string();
+        entry.unpack_in(".").with_path(path)?;
     }
     Ok(())
 }
@@ -175,8 +207,8 @@ fn untar<R: ?core::marker::Sized + io::Read>(stream: &mut R, context: &CliArgs)
 fn walk_dir<F: FnMut(&Path) -> io::Result<()>>(path: &Path, action: &mut F) -> io::Result<()> {
     action(path)?;
     if path.is_dir() {
-        for entry in fs::read_dir(path)? {
-            walk_dir(&entry?.path(), action)?;
+        for entry in fs::read_dir(path).with_path(path.display())? {
+            walk_dir(&entry.with_path(path.display())?.path(), action)?;
         }
     }
     Ok(())
@@ -185,15 +217,16 @@ fn walk_dir<F: FnMut(&Path) -> io::Result<()>>(path: &Path, action: &mut F) -> i
 fn create_tar<R: ?core::marker::Sized + io::Write>(
     stream: &mut R,
     context: &CliArgs,
+    log: &mut dyn io::Write,
 ) -> io::Result<()> {
     let mut builder = Builder::new(stream);
     for f in &context.files {
         let path = Path::new(f);
         walk_dir(path, &mut |p: &Path| -> io::Result<()> {
             if context.verbose {
-                println!("{}", p.display());
+                writeln!(log, "{}", p.display())?;
             }
-            builder.append_path(p)
+            builder.append_path(p).with_path(p.display())
         })?;
     }
     builder.finish()?;
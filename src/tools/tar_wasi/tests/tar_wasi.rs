@@ -0,0 +1,49 @@
+/*
+ * Copyright (c) 2026 Antmicro <www.antmicro.com>
+ *
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use std::fs;
+use std::path::PathBuf;
+
+use applet::Context;
+
+// Archived paths must be relative, so the file is created in the current
+// directory
+fn file(name: &str) -> PathBuf {
+    let path = PathBuf::from(format!(".wasibox-tar-{}-{}", name, std::process::id()));
+    fs::write(&path, "archived content").unwrap();
+    path
+}
+
+#[test]
+fn writes_archive_to_stdout() {
+    let path = file("stdout");
+    let mut out = Vec::new();
+    let mut ctx = Context::new(&b""[..], &mut out, Vec::new());
+
+    let result = tar_wasi::tar(["-c", "--files", path.to_str().unwrap()], &mut ctx);
+
+    fs::remove_file(&path).unwrap();
+    assert!(result.is_ok());
+    drop(ctx);
+    assert!(out
+        .windows(b"archived content".len())
+        .any(|window| window == b"archived content"));
+}
+
+#[test]
+fn refuses_to_write_archive_to_terminal() {
+    let path = file("terminal");
+    let mut out = Vec::new();
+    let mut ctx = Context::new(&b""[..], &mut out, Vec::new()).with_terminal_stdout(true);
+
+    let e = tar_wasi::tar(["-c", "--files", path.to_str().unwrap()], &mut ctx).unwrap_err();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(e.code(), 2);
+    assert_eq!(e.message(), Some("Refusing to write to stdout"));
+    drop(ctx);
+    assert!(out.is_empty());
+}
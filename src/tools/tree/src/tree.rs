@@ -4,22 +4,26 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 
-use std::env::Args;
+use std::io::Write;
 use std::{fs, io};
 
-use clap::Parser;
+use applet::errno::WithPath;
+use applet::{parse_args, AppletResult, Context, Manual, EXIT_FAILURE, EXIT_USAGE};
+use clap::{Command, CommandFactory, Parser, ValueHint};
 use std::fs::DirEntry;
 use std::path::{Path, PathBuf};
 
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
 fn visit_dirs(
     dir: &Path,
     current_path: &PathBuf,
     cli: &CliArgs,
-    cb: &dyn Fn(&PathBuf, &DirEntry),
+    cb: &mut dyn FnMut(&PathBuf, &DirEntry) -> io::Result<()>,
 ) -> io::Result<()> {
     if dir.is_dir() {
-        for entry in fs::read_dir(dir)? {
-            let entry = entry?;
+        for entry in fs::read_dir(dir).with_path(dir.display())? {
+            let entry = entry.with_path(dir.display())?;
             let path = entry.path();
             if !cli.all
                 && path
@@ -32,7 +36,7 @@ fn visit_dirs(
             {
                 continue;
             }
-            cb(current_path, &entry);
+            cb(current_path, &entry)?;
             if path.is_dir() {
                 visit_dirs(&path, &current_path.join(entry.file_name()), cli, cb)?;
             }
@@ -41,25 +45,54 @@ fn visit_dirs(
     Ok(())
 }
 
+/// List the contents of directories recursively
+///
+/// Prints the path of every file under the given directories, or under the
+/// current one, relative to the listed directory. Hidden files are skipped
+/// unless requested.
 #[derive(Parser)]
 #[command(no_binary_name = true)]
 struct CliArgs {
+    /// include hidden files
     #[arg(short, long)]
     all: bool,
+    /// directories to list
+    #[arg(value_hint = ValueHint::DirPath)]
     files: Vec<String>,
 }
 
-pub fn tree(args: Args) -> io::Result<()> {
-    let cli = CliArgs::parse_from(args);
+pub fn command() -> Command {
+    CliArgs::command()
+}
+
+pub const MANUAL: Manual = Manual {
+    exit_codes: &[
+        (0, "success"),
+        (EXIT_FAILURE, "a directory could not be read"),
+        (EXIT_USAGE, "invalid command line arguments"),
+    ],
+    examples: &[
+        ("tree", "list the current directory"),
+        ("tree -a /etc", "list /etc including hidden files"),
+    ],
+    sections: &[],
+};
+
+pub fn tree(args: impl IntoIterator<Item = impl Into<String>>, ctx: &mut Context) -> AppletResult {
+    let cli: CliArgs = parse_args(args, ctx)?;
 
-    if cli.files.is_empty() {
+    if !cli.files.is_empty() {
         for dir in &cli.files {
             visit_dirs(
                 Path::new(&dir),
                 &PathBuf::new(),
                 &cli,
-                &|current_path: &PathBuf, entry: &DirEntry| {
-                    println!("{}", current_path.join(entry.file_name()).display());
+                &mut |current_path: &PathBuf, entry: &DirEntry| {
+                    writeln!(
+                        ctx.stdout,
+                        "{}",
+                        current_path.join(entry.file_name()).display()
+                    )
                 },
             )?;
         }
@@ -68,8 +101,12 @@ pub fn tree(args: Args) -> io::Result<()> {
             Path::new("."),
             &PathBuf::new(),
             &cli,
-            &|current_path: &PathBuf, entry: &DirEntry| {
-                println!("{}", current_path.join(entry.file_name()).display());
+            &mut |current_path: &PathBuf, entry: &DirEntry| {
+                writeln!(
+                    ctx.stdout,
+                    "{}",
+                    current_path.join(entry.file_name()).display()
+                )
             },
         )?;
     }
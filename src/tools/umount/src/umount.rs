@@ -4,49 +4,81 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 
-use std::env;
-use std::fs::File;
-use std::io::{self, BufRead};
+use std::io::{self, BufRead, Write};
 
-use clap::Parser;
+use applet::errno::{self, WithPath};
+use applet::kernel::OpenFlags;
+use applet::{parse_args, AppletError, AppletResult, Context, Manual, EXIT_FAILURE, EXIT_USAGE};
+use clap::{Command, CommandFactory, Parser, ValueHint};
 
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+const MOUNTINFO_PATH: &str = "/proc/self/mountinfo";
+
+/// Unmount filesystems
+///
+/// Unmounts the filesystem mounted at the given mount point, or every
+/// filesystem except the root one. Failures to unmount with --all are
+/// reported without stopping.
 #[derive(Parser)]
 #[clap(no_binary_name = true)]
 struct CliArgs {
+    /// unmount every filesystem except the root one
     #[arg(short, long)]
     all: bool,
-    #[clap(required_unless_present("all"))]
+    /// mount point of the filesystem
+    #[clap(required_unless_present("all"), value_hint = ValueHint::DirPath)]
     mount_point: Option<String>,
 }
 
-pub fn umount(args: env::Args) -> io::Result<()> {
-    let cli = CliArgs::parse_from(args);
+pub fn command() -> Command {
+    CliArgs::command()
+}
+
+pub const MANUAL: Manual = Manual {
+    exit_codes: &[
+        (0, "success"),
+        (EXIT_FAILURE, "the filesystem could not be unmounted"),
+        (EXIT_USAGE, "invalid command line arguments"),
+    ],
+    examples: &[
+        ("umount /mnt", "unmount the filesystem at /mnt"),
+        ("umount -a", "unmount everything but the root filesystem"),
+    ],
+    sections: &[],
+};
+
+pub fn umount(
+    args: impl IntoIterator<Item = impl Into<String>>,
+    ctx: &mut Context,
+) -> AppletResult {
+    let cli: CliArgs = parse_args(args, ctx)?;
     if cli.all {
-        io::BufReader::new(File::open("/proc/self/mountinfo")?)
-            .lines()
-            .for_each(|line| {
-                let line = if let Ok(ln) = line {
-                    ln
-                } else {
-                    return;
-                };
-
-                let mount_point = line.split(' ').next().unwrap();
-                if mount_point == "/" {
-                    return;
-                }
-
-                if let Err(e) = wasi_ext_lib::umount(mount_point) {
-                    eprintln!("Could not umount {} - system error {}", mount_point, e);
-                }
-            });
+        let mountinfo = ctx
+            .kernel
+            .open(MOUNTINFO_PATH, OpenFlags::READ)
+            .with_path(MOUNTINFO_PATH)?;
+        for line in io::BufReader::new(mountinfo).lines() {
+            let line = if let Ok(ln) = line {
+                ln
+            } else {
+                continue;
+            };
+
+            let mount_point = line.split(' ').next().unwrap();
+            if mount_point == "/" {
+                continue;
+            }
+
+            if let Err(e) = ctx.kernel.umount(mount_point).with_path(mount_point) {
+                writeln!(ctx.stderr, "umount: {}", errno::describe(&e))?;
+            }
+        }
         Ok(())
     } else if let Some(point) = cli.mount_point {
-        wasi_ext_lib::umount(&point).map_err(io::Error::from_raw_os_error)
+        ctx.kernel.umount(&point).with_path(&point)?;
+        Ok(())
     } else {
-        Err(io::Error::new(
-            io::ErrorKind::InvalidInput,
-            "No mount point specified",
-        ))
+        Err(AppletError::new(EXIT_FAILURE, "No mount point specified"))
     }
 }
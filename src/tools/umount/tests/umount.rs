@@ -0,0 +1,72 @@
+/*
+ * Copyright (c) 2026 Antmicro <www.antmicro.com>
+ *
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use applet::kernel::Kernel;
+use applet::Context;
+use mock_kernel::MockKernel;
+
+fn run(kernel: &MockKernel, args: &[&str]) -> (Option<i32>, String) {
+    let mut err = Vec::new();
+    let mut ctx = Context::new(&b""[..], Vec::new(), &mut err).with_kernel(kernel.clone());
+    let code = umount::umount(args.iter().copied(), &mut ctx)
+        .err()
+        .map(|e| e.code());
+    drop(ctx);
+    (code, String::from_utf8(err).unwrap())
+}
+
+fn mount_points(kernel: &MockKernel) -> Vec<String> {
+    kernel.mounts().into_iter().map(|m| m.target).collect()
+}
+
+fn with_mounts(targets: &[&str]) -> MockKernel {
+    let mut kernel = MockKernel::new();
+    for target in targets {
+        kernel.mount("", target, "vfs", 0, "").unwrap();
+    }
+    kernel
+}
+
+#[test]
+fn unmounts_mount_point() {
+    let kernel = with_mounts(&["/mnt", "/tmp"]);
+
+    let (code, _) = run(&kernel, &["/mnt"]);
+
+    assert_eq!(code, None);
+    assert_eq!(mount_points(&kernel), ["/", "/tmp"]);
+}
+
+#[test]
+fn unmounts_all_but_root() {
+    let kernel = with_mounts(&["/mnt", "/tmp"]);
+
+    let (code, err) = run(&kernel, &["-a"]);
+
+    assert_eq!(code, None);
+    assert_eq!(err, "");
+    assert_eq!(mount_points(&kernel), ["/"]);
+}
+
+#[test]
+fn fails_on_missing_mount_point() {
+    let kernel = with_mounts(&["/mnt"]);
+
+    let (code, _) = run(&kernel, &["/tmp"]);
+
+    assert_eq!(code, Some(1));
+    assert_eq!(mount_points(&kernel), ["/", "/mnt"]);
+}
+
+#[test]
+fn requires_mount_point() {
+    let kernel = MockKernel::new();
+
+    let (code, err) = run(&kernel, &[]);
+
+    assert_eq!(code, Some(2));
+    assert!(err.contains("<MOUNT_POINT>"));
+}
@@ -4,12 +4,19 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 
-use std::env::Args;
-use std::io;
+use std::io::Write;
 
+use applet::kernel::NameType;
+use applet::{parse_args, AppletResult, Context, Manual, EXIT_FAILURE, EXIT_USAGE};
 use clap::ArgGroup;
-use clap::Parser;
+use clap::{Command, CommandFactory, Parser};
 
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Print information about the browser location
+///
+/// Prints a part of the URL the system is running at, or the user agent of
+/// the browser. The whole URL is printed by default.
 #[derive(Parser)]
 #[command(no_binary_name = true)]
 #[command(group(
@@ -18,64 +25,86 @@ use clap::Parser;
         .multiple(false)
 ))]
 struct CliArgs {
+    /// print the whole URL
     #[arg(short = 'H', long)]
     href: bool,
 
+    /// print the protocol, e.g. https:
     #[arg(short = 'p', long)]
     protocol: bool,
 
+    /// print the host with the port
     #[arg(short = 't', long)]
     host: bool,
 
+    /// print the port
     #[arg(short = 'P', long)]
     port: bool,
 
+    /// print the path
     #[arg(short = 'n', long)]
     pathname: bool,
 
+    /// print the query string, starting with ?
     #[arg(short = 's', long)]
     search: bool,
 
+    /// print the fragment, starting with #
     #[arg(short = 'S', long)]
     hash: bool,
 
+    /// print the protocol, host and port
     #[arg(short = 'o', long)]
     origin: bool,
 
+    /// print the user agent of the browser
     #[arg(short = 'u', long)]
     user_agent: bool,
 }
 
-pub fn uname(args: Args) -> io::Result<()> {
-    let cli = CliArgs::parse_from(args);
+pub fn command() -> Command {
+    CliArgs::command()
+}
+
+pub const MANUAL: Manual = Manual {
+    exit_codes: &[
+        (0, "success"),
+        (EXIT_FAILURE, "the information could not be retrieved"),
+        (EXIT_USAGE, "invalid command line arguments"),
+    ],
+    examples: &[
+        ("uname", "print the URL of the system"),
+        ("uname -t", "print the host the system was loaded from"),
+    ],
+    sections: &[],
+};
+
+pub fn uname(args: impl IntoIterator<Item = impl Into<String>>, ctx: &mut Context) -> AppletResult {
+    let cli: CliArgs = parse_args(args, ctx)?;
 
     let name_type = if cli.href {
-        wasi_ext_lib::NameType::Href
+        NameType::Href
     } else if cli.protocol {
-        wasi_ext_lib::NameType::Protocol
+        NameType::Protocol
     } else if cli.host {
-        wasi_ext_lib::NameType::Host
+        NameType::Host
     } else if cli.port {
-        wasi_ext_lib::NameType::Port
+        NameType::Port
     } else if cli.pathname {
-        wasi_ext_lib::NameType::Pathname
+        NameType::Pathname
     } else if cli.search {
-        wasi_ext_lib::NameType::Search
+        NameType::Search
     } else if cli.hash {
-        wasi_ext_lib::NameType::Hash
+        NameType::Hash
     } else if cli.origin {
-        wasi_ext_lib::NameType::Origin
+        NameType::Origin
     } else if cli.user_agent {
-        wasi_ext_lib::NameType::UserAgent
+        NameType::UserAgent
     } else {
-        wasi_ext_lib::NameType::Href
+        NameType::Href
     };
 
-    match wasi_ext_lib::uname(name_type) {
-        Ok(info) => {
-            println!("{}", info);
-            Ok(())
-        }
-        Err(code) => Err(io::Error::from_raw_os_error(code)),
-    }
+    let info = ctx.kernel.uname(name_type)?;
+    writeln!(ctx.stdout, "{}", info)?;
+    Ok(())
 }
@@ -4,62 +4,95 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 
-use std::env::Args;
 use std::fs;
 use std::io;
+use std::io::Write;
 use std::path::PathBuf;
 
-use clap::Parser;
+use applet::errno::WithPath;
+use applet::{parse_args, AppletError, AppletResult, Context, Manual, EXIT_FAILURE, EXIT_USAGE};
+use clap::{Command, CommandFactory, Parser, ValueHint};
 
 use zip::ZipArchive;
 
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Extract files from a zip archive
+///
+/// Extracts every file of the archive into the current directory, creating
+/// the missing directories.
 #[derive(Parser)]
 #[command(no_binary_name = true)]
 struct CliArgs {
+    /// don't print the extracted files
     #[arg(short, long)]
     quiet: bool,
+    /// archive to extract
+    #[arg(value_hint = ValueHint::FilePath)]
     file: String,
 }
 
-pub fn unzip(args: Args) -> io::Result<()> {
-    let cli = CliArgs::parse_from(args);
+pub fn command() -> Command {
+    CliArgs::command()
+}
+
+pub const MANUAL: Manual = Manual {
+    exit_codes: &[
+        (0, "success"),
+        (EXIT_FAILURE, "the archive could not be read or extracted"),
+        (EXIT_USAGE, "invalid command line arguments"),
+    ],
+    examples: &[("unzip -q project.zip", "extract project.zip quietly")],
+    sections: &[],
+};
+
+pub fn unzip(args: impl IntoIterator<Item = impl Into<String>>, ctx: &mut Context) -> AppletResult {
+    let cli: CliArgs = parse_args(args, ctx)?;
     let filepath: PathBuf = PathBuf::from(cli.file);
     // TODO: for some reason it is always true, it may be a bug in clap, maybe we should downgrade
     if !filepath.is_file() {
-        return Err(io::Error::new(
-            io::ErrorKind::InvalidInput,
+        return Err(AppletError::new(
+            EXIT_FAILURE,
             format!("Cannot find or open {}", filepath.display()),
         ));
     }
-    if let Ok(archive) = &mut ZipArchive::new(fs::File::open(filepath)?) {
+    if let Ok(archive) =
+        &mut ZipArchive::new(fs::File::open(&filepath).with_path(filepath.display())?)
+    {
         for i in 0..archive.len() {
             let mut file = archive.by_index(i).unwrap();
-            let output_path = file.enclosed_name().to_owned().unwrap();
+            let output_path = file.enclosed_name().unwrap().to_path_buf();
             if file.name().ends_with('/') {
                 if !cli.quiet {
-                    println!("creating dir {}", output_path.display());
+                    writeln!(ctx.stdout, "creating dir {}", output_path.display())?;
                 }
-                fs::create_dir_all(output_path).unwrap();
+                fs::create_dir_all(&output_path).with_path(output_path.display())?;
                 continue;
             }
             if let Some(parent) = output_path.parent() {
                 if !parent.exists() {
                     if !cli.quiet {
-                        println!("creating dir {}", parent.display());
+                        writeln!(ctx.stdout, "creating dir {}", parent.display())?;
                     }
-                    fs::create_dir_all(parent)?;
+                    fs::create_dir_all(parent).with_path(parent.display())?;
                 }
             }
             if !cli.quiet {
-                println!("decompressing {}", file.enclosed_name().unwrap().display());
+                writeln!(
+                    ctx.stdout,
+                    "decompressing {}",
+                    file.enclosed_name().unwrap().display()
+                )?;
             }
-            let mut output_file = fs::File::create(output_path).unwrap();
-            io::copy(&mut file, &mut output_file).unwrap();
+            let mut output_file =
+                fs::File::create(&output_path).with_path(output_path.display())?;
+            io::copy(&mut file, &mut output_file).with_path(output_path.display())?;
             if !cli.quiet {
-                println!(
+                writeln!(
+                    ctx.stdout,
                     "decompressing {} done.",
                     file.enclosed_name().unwrap().display()
-                );
+                )?;
             }
         }
     }
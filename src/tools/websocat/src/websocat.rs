@@ -4,97 +4,94 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 
-use std::env;
-use std::fs;
-use std::io;
 use std::io::Read;
 use std::io::Write;
-use std::mem;
-use std::os::fd::AsRawFd;
 
-use clap::Parser;
+use applet::errno::WithPath;
+use applet::kernel::{OpenFlags, PollEvent, STDIN_FD};
+use applet::{parse_args, AppletResult, Context, Manual, EXIT_FAILURE, EXIT_USAGE};
+use clap::{Command, CommandFactory, Parser, ValueHint};
 
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Connect stdin and stdout to a WebSocket
+///
+/// Sends the standard input to the server and prints the messages received
+/// from it, until the server closes the connection.
 #[derive(Parser)]
 #[command(no_binary_name = true)]
 struct CliArgs {
+    /// address of the server
+    #[arg(value_hint = ValueHint::Url)]
     url: String,
 }
 
-const TOKEN_STDIN: u64 = 0;
-const TOKEN_SOCKET: u64 = 1;
-
-pub fn websocat(args: env::Args) -> io::Result<()> {
-    let cli = CliArgs::parse_from(args);
-    let mut buf = [0u8; 8192];
+pub fn command() -> Command {
+    CliArgs::command()
+}
 
-    let minor = fs::OpenOptions::new()
-        .write(true)
-        .open("/dev/ws0")?
-        .write(cli.url.as_bytes())?;
+pub const MANUAL: Manual = Manual {
+    exit_codes: &[
+        (0, "the server closed the connection"),
+        (
+            EXIT_FAILURE,
+            "the connection could not be established or failed",
+        ),
+        (EXIT_USAGE, "invalid command line arguments"),
+    ],
+    examples: &[(
+        "echo hello | websocat ws://localhost:8080",
+        "send hello and print the replies",
+    )],
+    sections: &[],
+};
 
-    let mut connection_device = fs::OpenOptions::new()
-        .write(true)
-        .read(true)
-        .open(format!("/dev/ws0s{}", minor))?;
+// stdin is polled through the file descriptor of the process, so the
+// stdin of the context should be the process stdin or read from it.
+pub fn websocat(
+    args: impl IntoIterator<Item = impl Into<String>>,
+    ctx: &mut Context,
+) -> AppletResult {
+    let cli: CliArgs = parse_args(args, ctx)?;
+    let mut buf = [0u8; 8192];
 
-    let subs = [
-        wasi::Subscription {
-            userdata: TOKEN_SOCKET,
-            u: wasi::SubscriptionU {
-                tag: wasi::EVENTTYPE_FD_READ.raw(),
-                u: wasi::SubscriptionUU {
-                    fd_read: wasi::SubscriptionFdReadwrite {
-                        file_descriptor: connection_device.as_raw_fd() as u32,
-                    },
-                },
-            },
-        },
-        wasi::Subscription {
-            userdata: TOKEN_STDIN,
-            u: wasi::SubscriptionU {
-                tag: wasi::EVENTTYPE_FD_READ.raw(),
-                u: wasi::SubscriptionUU {
-                    fd_read: wasi::SubscriptionFdReadwrite {
-                        file_descriptor: io::stdin().as_raw_fd() as u32,
-                    },
-                },
-            },
-        },
-    ];
+    let minor = ctx
+        .kernel
+        .open("/dev/ws0", OpenFlags::WRITE)
+        .with_path("/dev/ws0")?
+        .write(cli.url.as_bytes())
+        .with_path(&cli.url)?;
 
-    let mut events: [wasi::Event; 2] = unsafe { mem::zeroed() };
+    let connection_path = format!("/dev/ws0s{}", minor);
+    let mut connection_device = ctx
+        .kernel
+        .open(&connection_path, OpenFlags::READ_WRITE)
+        .with_path(&connection_path)?;
+    let socket = connection_device.fd();
+    let mut fds = vec![socket, STDIN_FD];
 
     loop {
-        let count = unsafe {
-            match wasi::poll_oneoff(subs.as_ptr(), events.as_mut_ptr(), subs.len()) {
-                Ok(n) => n,
-                Err(e) => {
-                    return Err(io::Error::from_raw_os_error(e.raw() as i32));
+        for event in ctx.kernel.poll(&fds, None)? {
+            match event {
+                PollEvent::Readable(fd) if fd == socket => {
+                    let len = connection_device.read(&mut buf).with_path(&cli.url)?;
+                    ctx.stdout.write_all(&buf[..len])?;
+                    ctx.stdout.flush()?;
                 }
-            }
-        };
-
-        if count == 0 {
-            continue;
-        }
-
-        for event in events[0..count].iter() {
-            match event.userdata {
-                TOKEN_SOCKET => {
-                    if event.type_ == wasi::EVENTTYPE_FD_READ {
-                        let len = connection_device.read(&mut buf)?;
-                        _ = io::stdout().write(&buf[..len])?;
-                    } else {
-                        eprintln!("Connection closed by the server");
-                        return Ok(());
-                    }
+                PollEvent::Hangup(fd) if fd == socket => {
+                    writeln!(ctx.stderr, "Connection closed by the server")?;
+                    return Ok(());
                 }
                 _ => {
-                    let len = io::stdin().read(&mut buf)?;
-                    _ = connection_device.write(&buf[..len])?;
+                    let len = ctx.stdin.read(&mut buf)?;
+                    if len == 0 {
+                        // Keep receiving after the end of the input
+                        fds.retain(|fd| *fd != STDIN_FD);
+                        continue;
+                    }
+                    _ = connection_device.write(&buf[..len]).with_path(&cli.url)?;
                 }
             }
         }
-        events = unsafe { mem::zeroed() };
     }
 }
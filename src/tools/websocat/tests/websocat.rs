@@ -0,0 +1,59 @@
+/*
+ * Copyright (c) 2026 Antmicro <www.antmicro.com>
+ *
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use applet::Context;
+use mock_kernel::MockKernel;
+
+const URL: &str = "ws://example.com/echo";
+
+fn run(kernel: &MockKernel, input: &[u8], args: &[&str]) -> (Option<i32>, String, String) {
+    let mut out = Vec::new();
+    let mut err = Vec::new();
+    let mut ctx = Context::new(input, &mut out, &mut err).with_kernel(kernel.clone());
+    let code = websocat::websocat(args.iter().copied(), &mut ctx)
+        .err()
+        .map(|e| e.code());
+    drop(ctx);
+    (
+        code,
+        String::from_utf8(out).unwrap(),
+        String::from_utf8(err).unwrap(),
+    )
+}
+
+#[test]
+fn prints_received_messages() {
+    let kernel = MockKernel::new();
+    kernel.add_ws_server(URL, &[b"first\n", b"second\n"]);
+
+    let (code, out, err) = run(&kernel, b"", &[URL]);
+
+    assert_eq!(code, None);
+    assert_eq!(out, "first\nsecond\n");
+    assert_eq!(err, "Connection closed by the server\n");
+}
+
+#[test]
+fn sends_input() {
+    let kernel = MockKernel::new();
+    kernel.add_ws_server(URL, &[b"welcome\n"]);
+
+    let (code, out, _) = run(&kernel, b"hello\n", &[URL]);
+
+    assert_eq!(code, None);
+    assert_eq!(out, "welcome\n");
+    assert_eq!(kernel.ws_received(URL), b"hello\n");
+}
+
+#[test]
+fn fails_on_refused_connection() {
+    let kernel = MockKernel::new();
+
+    let (code, out, _) = run(&kernel, b"", &[URL]);
+
+    assert_eq!(code, Some(1));
+    assert_eq!(out, "");
+}
@@ -4,81 +4,135 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 
-use std::env;
-use std::fs;
 use std::io;
 use std::io::Write;
-use std::os::fd::AsRawFd;
 
-use clap::Parser;
+use applet::errno::{self, WithPath};
+use applet::kernel::{Ioctl, OpenFlags};
+use applet::{parse_args, AppletError, AppletResult, Context, Manual, EXIT_FAILURE, EXIT_USAGE};
+use clap::{Command, CommandFactory, Parser, ValueHint};
 
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// Exit status used when the server responds with an error, as in GNU wget
+const EXIT_SERVER_ERROR: i32 = 8;
+
+/// Download a file over HTTP
+///
+/// Saves the file in the current directory, named after the last segment of
+/// the URL, unless another path is given.
 #[derive(Parser)]
 #[command(no_binary_name = true)]
 struct CliArgs {
-    #[arg(short('O'), long)]
+    /// write the document to FILE, or to stdout if FILE is -
+    #[arg(short('O'), long, value_name = "FILE", value_hint = ValueHint::FilePath)]
     output_document: Option<String>,
+    /// print the status and headers of the response
     #[arg(short('S'), long)]
     server_response: bool,
+    /// save the document even if the server responds with an error
     #[arg(long)]
     content_on_error: bool,
+    /// address of the file
+    #[arg(value_hint = ValueHint::Url)]
     url: String,
 }
 
-pub fn wget(args: env::Args) -> io::Result<()> {
-    let cli = CliArgs::parse_from(args);
+pub fn command() -> Command {
+    CliArgs::command()
+}
+
+pub const MANUAL: Manual = Manual {
+    exit_codes: &[
+        (0, "success"),
+        (
+            EXIT_FAILURE,
+            "the request failed or the file could not be written",
+        ),
+        (EXIT_USAGE, "invalid command line arguments"),
+        (EXIT_SERVER_ERROR, "the server responded with an error"),
+    ],
+    examples: &[
+        (
+            "wget https://example.com/index.html",
+            "save the page as index.html",
+        ),
+        (
+            "wget -S -O - https://example.com/",
+            "print the response headers and the page",
+        ),
+    ],
+    sections: &[],
+};
+
+pub fn wget(args: impl IntoIterator<Item = impl Into<String>>, ctx: &mut Context) -> AppletResult {
+    let cli: CliArgs = parse_args(args, ctx)?;
 
-    let minor = fs::OpenOptions::new()
-        .write(true)
-        .open("/dev/wget0")?
-        .write(cli.url.as_bytes())?;
-    let response_device = fs::File::open(format!("/dev/wget0r{}", minor))?;
+    let minor = ctx
+        .kernel
+        .open("/dev/wget0", OpenFlags::WRITE)
+        .with_path("/dev/wget0")?
+        .write(cli.url.as_bytes())
+        .with_path(&cli.url)?;
+    let response_path = format!("/dev/wget0r{}", minor);
+    let mut response_device = ctx
+        .kernel
+        .open(&response_path, OpenFlags::READ)
+        .with_path(&response_path)?;
 
     let mut http_stat: i32 = 0;
-    if let Err(e) = wasi_ext_lib::ioctl(
-        response_device.as_raw_fd(),
-        wasi_ext_lib::WGETGS,
-        Some(&mut http_stat),
-    ) {
-        eprintln!("Could not retreive http status: system error {}", e);
+    if let Err(e) = response_device
+        .ioctl(Ioctl::WgetStatus, Some(&mut http_stat))
+        .with_path(&cli.url)
+    {
+        writeln!(ctx.stderr, "wget: {}", errno::describe(&e))?;
     }
     if cli.server_response {
-        if let Err(e) =
-            wasi_ext_lib::ioctl::<()>(response_device.as_raw_fd(), wasi_ext_lib::WGETRH, None)
+        if let Err(e) = response_device
+            .ioctl(Ioctl::WgetHeaders, None)
+            .with_path(&cli.url)
         {
-            eprintln!("Could not read http headers: system error {}", e);
+            writeln!(ctx.stderr, "wget: {}", errno::describe(&e))?;
         } else {
-            println!("HTTP {}", http_stat);
+            writeln!(ctx.stdout, "HTTP {}", http_stat)?;
             std::io::copy(
-                &mut io::BufReader::new(&response_device),
-                &mut io::BufWriter::new(std::io::stdout()),
+                &mut io::BufReader::new(&mut response_device),
+                &mut ctx.stdout,
             )?;
-            println!("\n");
+            writeln!(ctx.stdout, "\n")?;
         }
     }
 
     if http_stat >= 300 && !cli.content_on_error {
-        eprintln!("ERROR {}", http_stat);
-        std::process::exit(8);
+        return Err(AppletError::new(
+            EXIT_SERVER_ERROR,
+            format!("ERROR {}", http_stat),
+        ));
     }
 
-    if let Err(e) =
-        wasi_ext_lib::ioctl::<()>(response_device.as_raw_fd(), wasi_ext_lib::WGETRB, None)
+    if let Err(e) = response_device
+        .ioctl(Ioctl::WgetBody, None)
+        .with_path(&cli.url)
     {
-        eprintln!("Could not read http body: system error {}", e);
+        writeln!(ctx.stderr, "wget: {}", errno::describe(&e))?;
     } else {
-        let mut writer: Box<dyn io::Write> = if let Some(s) = cli.output_document {
+        let mut writer: Box<dyn io::Write + '_> = if let Some(s) = cli.output_document {
             if s.as_str() == "-" {
-                Box::new(io::BufWriter::new(io::stdout()))
+                Box::new(io::BufWriter::new(&mut ctx.stdout))
             } else {
-                Box::new(io::BufWriter::new(fs::File::create(s)?))
+                Box::new(io::BufWriter::new(
+                    ctx.kernel.open(&s, OpenFlags::CREATE).with_path(&s)?,
+                ))
             }
         } else {
-            Box::new(io::BufWriter::new(fs::File::create(
-                cli.url.rsplit('/').next().unwrap(),
-            )?))
+            let path = cli.url.rsplit('/').next().unwrap();
+            Box::new(io::BufWriter::new(
+                ctx.kernel.open(path, OpenFlags::CREATE).with_path(path)?,
+            ))
         };
         let mut reader = io::BufReader::new(response_device);
         std::io::copy(&mut reader, &mut writer)?;
+        writer.flush()?;
     }
 
     Ok(())
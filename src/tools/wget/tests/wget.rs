@@ -0,0 +1,114 @@
+/*
+ * Copyright (c) 2026 Antmicro <www.antmicro.com>
+ *
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use applet::Context;
+use mock_kernel::MockKernel;
+
+const URL: &str = "http://example.com/files/hello.txt";
+
+fn run(kernel: &MockKernel, args: &[&str]) -> (Option<i32>, String, String) {
+    let mut out = Vec::new();
+    let mut err = Vec::new();
+    let mut ctx = Context::new(&b""[..], &mut out, &mut err).with_kernel(kernel.clone());
+    let code = wget::wget(args.iter().copied(), &mut ctx)
+        .err()
+        .map(|e| e.code());
+    drop(ctx);
+    (
+        code,
+        String::from_utf8(out).unwrap(),
+        String::from_utf8(err).unwrap(),
+    )
+}
+
+#[test]
+fn downloads_to_stdout() {
+    let kernel = MockKernel::new();
+    kernel.add_http_response(URL, 200, "content-type: text/plain", b"Hello!\n");
+
+    let (code, out, _) = run(&kernel, &["-O", "-", URL]);
+
+    assert_eq!(code, None);
+    assert_eq!(out, "Hello!\n");
+    assert_eq!(kernel.wget_requests(), [URL]);
+}
+
+#[test]
+fn saves_to_last_url_segment_by_default() {
+    let kernel = MockKernel::new();
+    kernel.add_http_response(URL, 200, "", b"Hello!\n");
+
+    let (code, out, _) = run(&kernel, &[URL]);
+
+    assert_eq!(code, None);
+    assert_eq!(out, "");
+    assert_eq!(kernel.file("hello.txt").unwrap(), b"Hello!\n");
+}
+
+#[test]
+fn saves_to_output_document() {
+    let kernel = MockKernel::new();
+    kernel.add_file("/tmp/out", "old contents");
+    kernel.add_http_response(URL, 200, "", b"Hello!\n");
+
+    let (code, _, _) = run(&kernel, &["-O", "/tmp/out", URL]);
+
+    assert_eq!(code, None);
+    assert_eq!(kernel.file("/tmp/out").unwrap(), b"Hello!\n");
+}
+
+#[test]
+fn prints_server_response() {
+    let kernel = MockKernel::new();
+    kernel.add_http_response(URL, 200, "content-type: text/plain", b"Hello!\n");
+
+    let (code, out, _) = run(&kernel, &["-S", "-O", "-", URL]);
+
+    assert_eq!(code, None);
+    assert_eq!(out, "HTTP 200\ncontent-type: text/plain\n\nHello!\n");
+}
+
+#[test]
+fn fails_on_server_error() {
+    let kernel = MockKernel::new();
+    kernel.add_http_response(URL, 500, "", b"Internal error");
+
+    let (code, out, _) = run(&kernel, &["-O", "-", URL]);
+
+    assert_eq!(code, Some(8));
+    assert_eq!(out, "");
+}
+
+#[test]
+fn fails_on_unknown_url() {
+    let kernel = MockKernel::new();
+
+    let (code, _, _) = run(&kernel, &["-O", "-", URL]);
+
+    assert_eq!(code, Some(8));
+}
+
+#[test]
+fn writes_content_on_error() {
+    let kernel = MockKernel::new();
+    kernel.add_http_response(URL, 404, "", b"Not here");
+
+    let (code, out, _) = run(&kernel, &["--content-on-error", "-O", "-", URL]);
+
+    assert_eq!(code, None);
+    assert_eq!(out, "Not here");
+}
+
+#[test]
+fn requires_url() {
+    let kernel = MockKernel::new();
+
+    let (code, _, err) = run(&kernel, &[]);
+
+    assert_eq!(code, Some(2));
+    assert!(err.contains("<URL>"));
+    assert!(kernel.wget_requests().is_empty());
+}
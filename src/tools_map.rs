@@ -1,37 +1,284 @@
 /*
- * Copyright (c) 2022-2023 Antmicro <www.antmicro.com>
+ * Copyright (c) 2022-2026 Antmicro <www.antmicro.com>
  *
  * SPDX-License-Identifier: Apache-2.0
  */
 
+use applet::{AppletResult, Context, Manual};
+use clap::Command;
 use lazy_static::lazy_static;
-use std::collections::HashMap;
-use std::env::Args;
-use std::io::Result;
+use std::collections::BTreeMap;
+use std::path::Path;
 
-pub type AppletType = fn(Args) -> Result<()>;
+pub type AppletType = fn(Vec<String>, &mut Context) -> AppletResult;
+
+pub struct Applet {
+    pub name: &'static str,
+    /// Other names the applet can be invoked with
+    pub aliases: &'static [&'static str],
+    pub version: &'static str,
+    pub main: AppletType,
+    /// Command line interface of the applet, used for its help, shell
+    /// completions and manual page
+    pub command: fn() -> Command,
+    pub manual: &'static Manual,
+}
+
+impl Applet {
+    /// One-line description shown in the tool listing
+    pub fn summary(&self) -> String {
+        (self.command)()
+            .get_about()
+            .map(ToString::to_string)
+            .unwrap_or_default()
+    }
+}
+
+pub static APPLETS: &[Applet] = &[
+    #[cfg(feature = "free")]
+    Applet {
+        name: "free",
+        aliases: &[],
+        version: free::VERSION,
+        main: free::free,
+        command: free::command,
+        manual: &free::MANUAL,
+    },
+    #[cfg(feature = "hexdump")]
+    Applet {
+        name: "hexdump",
+        aliases: &["hd"],
+        version: hexdump::VERSION,
+        main: hexdump::hexdump,
+        command: hexdump::command,
+        manual: &hexdump::MANUAL,
+    },
+    #[cfg(feature = "imgcat")]
+    Applet {
+        name: "imgcat",
+        aliases: &[],
+        version: imgcat::VERSION,
+        main: imgcat::imgcat,
+        command: imgcat::command,
+        manual: &imgcat::MANUAL,
+    },
+    #[cfg(feature = "init")]
+    Applet {
+        name: "init",
+        aliases: &[],
+        version: init::VERSION,
+        main: init::init,
+        command: init::command,
+        manual: &init::MANUAL,
+    },
+    #[cfg(feature = "initctl")]
+    Applet {
+        name: "initctl",
+        aliases: &[],
+        version: initctl::VERSION,
+        main: initctl::initctl,
+        command: initctl::command,
+        manual: &initctl::MANUAL,
+    },
+    #[cfg(feature = "kill")]
+    Applet {
+        name: "kill",
+        aliases: &[],
+        version: kill::VERSION,
+        main: kill::kill,
+        command: kill::command,
+        manual: &kill::MANUAL,
+    },
+    #[cfg(feature = "mknod")]
+    Applet {
+        name: "mknod",
+        aliases: &[],
+        version: mknod::VERSION,
+        main: mknod::mknod,
+        command: mknod::command,
+        manual: &mknod::MANUAL,
+    },
+    #[cfg(feature = "mount")]
+    Applet {
+        name: "mount",
+        aliases: &[],
+        version: mount::VERSION,
+        main: mount::mount,
+        command: mount::command,
+        manual: &mount::MANUAL,
+    },
+    #[cfg(feature = "poweroff")]
+    Applet {
+        name: "poweroff",
+        aliases: &[],
+        version: poweroff::VERSION,
+        main: poweroff::poweroff,
+        command: poweroff::command,
+        manual: &poweroff::MANUAL,
+    },
+    #[cfg(feature = "ps")]
+    Applet {
+        name: "ps",
+        aliases: &[],
+        version: ps::VERSION,
+        main: ps::ps,
+        command: ps::command,
+        manual: &ps::MANUAL,
+    },
+    #[cfg(feature = "purge")]
+    Applet {
+        name: "purge",
+        aliases: &[],
+        version: purge::VERSION,
+        main: purge::purge,
+        command: purge::command,
+        manual: &purge::MANUAL,
+    },
+    #[cfg(feature = "poweroff")]
+    Applet {
+        name: "reboot",
+        aliases: &[],
+        version: poweroff::VERSION,
+        main: poweroff::reboot,
+        command: poweroff::reboot_command,
+        manual: &poweroff::REBOOT_MANUAL,
+    },
+    #[cfg(feature = "reset")]
+    Applet {
+        name: "reset",
+        aliases: &[],
+        version: reset::VERSION,
+        main: reset::reset,
+        command: reset::command,
+        manual: &reset::MANUAL,
+    },
+    #[cfg(feature = "stty")]
+    Applet {
+        name: "stty",
+        aliases: &[],
+        version: stty::VERSION,
+        main: stty::stty,
+        command: stty::command,
+        manual: &stty::MANUAL,
+    },
+    #[cfg(feature = "tar")]
+    Applet {
+        name: "tar",
+        aliases: &["gtar"],
+        version: tar_wasi::VERSION,
+        main: tar_wasi::tar,
+        command: tar_wasi::command,
+        manual: &tar_wasi::MANUAL,
+    },
+    #[cfg(feature = "tree")]
+    Applet {
+        name: "tree",
+        aliases: &[],
+        version: tree::VERSION,
+        main: tree::tree,
+        command: tree::command,
+        manual: &tree::MANUAL,
+    },
+    #[cfg(feature = "umount")]
+    Applet {
+        name: "umount",
+        aliases: &[],
+        version: umount::VERSION,
+        main: umount::umount,
+        command: umount::command,
+        manual: &umount::MANUAL,
+    },
+    #[cfg(feature = "uname")]
+    Applet {
+        name: "uname",
+        aliases: &[],
+        version: uname::VERSION,
+        main: uname::uname,
+        command: uname::command,
+        manual: &uname::MANUAL,
+    },
+    #[cfg(feature = "unzip")]
+    Applet {
+        name: "unzip",
+        aliases: &[],
+        version: unzip::VERSION,
+        main: unzip::unzip,
+        command: unzip::command,
+        manual: &unzip::MANUAL,
+    },
+    #[cfg(feature = "websocat")]
+    Applet {
+        name: "websocat",
+        aliases: &[],
+        version: websocat::VERSION,
+        main: websocat::websocat,
+        command: websocat::command,
+        manual: &websocat::MANUAL,
+    },
+    #[cfg(feature = "wget")]
+    Applet {
+        name: "wget",
+        aliases: &[],
+        version: wget::VERSION,
+        main: wget::wget,
+        command: wget::command,
+        manual: &wget::MANUAL,
+    },
+];
+
+// Misspelled names further than this from every applet get no suggestion
+const MAX_SUGGESTION_DISTANCE: usize = 2;
 
 lazy_static! {
-    pub static ref TOOLS_MAP: HashMap<&'static str, AppletType> = {
-        let mut m: HashMap<&'static str, AppletType> = HashMap::new();
-        m.insert("unzip", unzip::unzip);
-        m.insert("hexdump", hexdump::hexdump);
-        m.insert("imgcat", imgcat::imgcat);
-        m.insert("purge", purge::purge);
-        m.insert("tree", tree::tree);
-        m.insert("tar", tar_wasi::tar);
-        m.insert("kill", kill::kill);
-        m.insert("stty", stty::stty);
-        m.insert("mount", mount::mount);
-        m.insert("umount", umount::umount);
-        m.insert("wget", wget::wget);
-        m.insert("mknod", mknod::mknod);
-        m.insert("init", init::init);
-        m.insert("websocat", websocat::websocat);
-        m.insert("uname", uname::uname);
-        m.insert("free", free::free);
-        m.insert("ps", ps::ps);
-        m.insert("reset", reset::reset);
+    pub static ref TOOLS_MAP: BTreeMap<&'static str, &'static Applet> = {
+        let mut m: BTreeMap<&'static str, &'static Applet> = BTreeMap::new();
+        for applet in APPLETS {
+            m.insert(applet.name, applet);
+            for alias in applet.aliases {
+                m.insert(alias, applet);
+            }
+        }
         m
     };
 }
+
+/// Looks up an applet by its name or one of its aliases.
+pub fn find_applet(name: &str) -> Option<&'static Applet> {
+    TOOLS_MAP.get(name).copied()
+}
+
+/// Reduces an argv[0] style command to the name it should be looked up by,
+/// e.g. `/usr/bin/tar.wasm` becomes `tar`.
+pub fn applet_name(command: &str) -> &str {
+    let name = Path::new(command)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(command);
+    name.strip_suffix(".wasm").unwrap_or(name)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<char>>();
+    let mut row = (0..=b.len()).collect::<Vec<usize>>();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Finds the applet name or alias closest to a misspelled one.
+pub fn suggest_applet(name: &str) -> Option<&'static str> {
+    TOOLS_MAP
+        .keys()
+        .map(|candidate| (edit_distance(name, candidate), *candidate))
+        .filter(|(distance, _)| *distance <= MAX_SUGGESTION_DISTANCE)
+        .min()
+        .map(|(_, candidate)| candidate)
+}
@@ -0,0 +1,33 @@
+/*
+ * Copyright (c) 2026 Antmicro <www.antmicro.com>
+ *
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use wasibox::completions::{shell, write_completions, SHELLS};
+use wasibox::tools_map::APPLETS;
+
+#[test]
+fn completes_every_applet() {
+    for name in SHELLS {
+        let mut out = Vec::new();
+        write_completions(&mut out, shell(name).unwrap());
+        let script = String::from_utf8(out).unwrap();
+
+        for applet in APPLETS {
+            for command in std::iter::once(&applet.name).chain(applet.aliases) {
+                assert!(
+                    script.contains(&format!("{} ", command)),
+                    "{} completions miss {}",
+                    name,
+                    command
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn rejects_unknown_shell() {
+    assert!(shell("tcsh").is_none());
+}
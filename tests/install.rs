@@ -0,0 +1,131 @@
+/*
+ * Copyright (c) 2026 Antmicro <www.antmicro.com>
+ *
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use wasibox::install::{arg_binary_path, install};
+use wasibox::tools_map::TOOLS_MAP;
+
+fn empty_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("wasibox-install-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn binary(dir: &Path) -> PathBuf {
+    let binary = dir.join("wasibox");
+    fs::write(&binary, "binary").unwrap();
+    binary
+}
+
+#[test]
+fn lists_links_without_creating_them() {
+    let dir = empty_dir("list");
+    let mut out = Vec::new();
+
+    install(&mut out, &dir, &dir.join("wasibox"), false, true).unwrap();
+
+    let expected = TOOLS_MAP
+        .keys()
+        .map(|name| format!("{}\n", dir.join(name).display()))
+        .collect::<String>();
+    assert_eq!(String::from_utf8(out).unwrap(), expected);
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+}
+
+#[test]
+fn creates_links() {
+    let dir = empty_dir("create");
+    let binary = binary(&dir);
+
+    install(&mut io::sink(), &dir, &binary, false, false).unwrap();
+
+    for name in TOOLS_MAP.keys() {
+        assert_eq!(fs::read(dir.join(name)).unwrap(), b"binary");
+    }
+}
+
+#[test]
+fn keeps_existing_files_without_force() {
+    let dir = empty_dir("exists");
+    let binary = binary(&dir);
+    let link = dir.join(TOOLS_MAP.keys().next().unwrap());
+    fs::write(&link, "old").unwrap();
+
+    let err = install(&mut io::sink(), &dir, &binary, false, false).unwrap_err();
+
+    assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+    assert_eq!(fs::read(&link).unwrap(), b"old");
+}
+
+#[test]
+fn replaces_existing_files_with_force() {
+    let dir = empty_dir("force");
+    let binary = binary(&dir);
+    let link = dir.join(TOOLS_MAP.keys().next().unwrap());
+    fs::write(&link, "old").unwrap();
+
+    install(&mut io::sink(), &dir, &binary, true, false).unwrap();
+
+    assert_eq!(fs::read(&link).unwrap(), b"binary");
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), TOOLS_MAP.len() + 1);
+}
+
+#[test]
+fn refuses_to_replace_directories() {
+    let dir = empty_dir("directory");
+    let binary = binary(&dir);
+    let link = dir.join(TOOLS_MAP.keys().next().unwrap());
+    fs::create_dir(&link).unwrap();
+
+    let err = install(&mut io::sink(), &dir, &binary, true, false).unwrap_err();
+
+    assert_eq!(err.kind(), io::ErrorKind::IsADirectory);
+    assert_eq!(
+        err.to_string(),
+        format!("{}: Is a directory", link.display())
+    );
+    assert!(link.is_dir());
+}
+
+#[test]
+fn fails_on_missing_directory() {
+    let dir = empty_dir("missing").join("missing");
+
+    let err = install(&mut io::sink(), &dir, &dir.join("wasibox"), false, false).unwrap_err();
+
+    assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    assert_eq!(
+        err.to_string(),
+        format!("{}: Not a directory", dir.display())
+    );
+}
+
+#[test]
+fn makes_binary_path_absolute() {
+    let cwd = env::current_dir().unwrap();
+
+    assert_eq!(
+        arg_binary_path(Some(String::from("./wasibox"))),
+        cwd.join("wasibox")
+    );
+    assert_eq!(
+        arg_binary_path(Some(String::from("bin/wasibox"))),
+        cwd.join("bin/wasibox")
+    );
+    assert_eq!(
+        arg_binary_path(Some(String::from("/opt/wasibox"))),
+        Path::new("/opt/wasibox")
+    );
+    assert_eq!(
+        arg_binary_path(Some(String::from("wasibox"))),
+        Path::new("/usr/bin/wasibox")
+    );
+}
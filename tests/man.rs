@@ -0,0 +1,63 @@
+/*
+ * Copyright (c) 2026 Antmicro <www.antmicro.com>
+ *
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use wasibox::man::write_manual;
+use wasibox::tools_map::APPLETS;
+
+#[test]
+fn describes_every_applet() {
+    for applet in APPLETS {
+        (applet.command)().name(applet.name).debug_assert();
+        assert!(
+            !applet.summary().is_empty(),
+            "{} has no summary",
+            applet.name
+        );
+        assert!(
+            !applet.manual.exit_codes.is_empty(),
+            "{} documents no exit codes",
+            applet.name
+        );
+        assert!(
+            !applet.manual.examples.is_empty(),
+            "{} has no examples",
+            applet.name
+        );
+    }
+}
+
+#[test]
+fn renders_every_manual() {
+    for applet in APPLETS {
+        let mut out = Vec::new();
+        write_manual(&mut out, applet).unwrap();
+        let page = String::from_utf8(out).unwrap();
+
+        for section in ["NAME", "SYNOPSIS", "DESCRIPTION", "EXIT STATUS", "EXAMPLES"] {
+            assert!(
+                page.lines().any(|line| line == section),
+                "{} manual misses {}",
+                applet.name,
+                section
+            );
+        }
+        let mut lines = page.lines().skip_while(|line| *line != "NAME");
+        assert!(lines
+            .nth(1)
+            .is_some_and(|line| line.trim_start().starts_with(applet.name)));
+        for (title, _) in applet.manual.sections {
+            assert!(
+                page.lines().any(|line| line == *title),
+                "{} manual misses {}",
+                applet.name,
+                title
+            );
+        }
+        for (example, _) in applet.manual.examples {
+            assert!(page.contains(example));
+        }
+    }
+}